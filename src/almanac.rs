@@ -1,168 +1,354 @@
+use crate::error::{parse_token, ParseError};
+use core::iter::Enumerate;
 use core::str::Lines;
-
-pub fn min_location_ex1(a: &Almanac) -> u64 {
-    a.seeds_ex1()
-        .iter()
-        .map(|seed| a.location(*seed))
-        .min()
-        .unwrap()
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+/// Returns the lowest location of the seeds, or `None` if there are no seeds
+/// or no maps leading from seeds to locations.
+pub fn min_location_ex1(a: &Almanac) -> Option<u64> {
+    let mapping = a.mapping("seed", "location")?;
+    a.seeds_ex1().iter().map(|seed| mapping.get(*seed)).min()
 }
 
-pub fn min_location_ex2(a: &Almanac) -> u64 {
-    a.location_intervals()[0].start()
+/// Returns the lowest location of the seed ranges, or `None` if there are no
+/// seeds, an odd number of them, or no maps leading from seeds to locations.
+pub fn min_location_ex2(a: &Almanac) -> Option<u64> {
+    if a.seeds_ex1().len() % 2 == 1 {
+        return None;
+    }
+    a.convert_intervals(a.seed_intervals(), "seed", "location")?
+        .intervals()
+        .first()
+        .map(|interval| interval.start())
 }
 
+/// Seeds and the maps between categories, one per `X-to-Y map:` section.
 pub struct Almanac {
     seeds_ex1: Vec<u64>,
     seed_intervals: Vec<Interval>,
-    seed2soil: IntervalMapping,
-    soil2fertilizer: IntervalMapping,
-    fertilizer2water: IntervalMapping,
-    water2light: IntervalMapping,
-    light2temperature: IntervalMapping,
-    temperature2humidity: IntervalMapping,
-    humidity2location: IntervalMapping,
+    stages: Vec<Stage>,
+}
+
+/// The map from one category to another.
+struct Stage {
+    from: String,
+    to: String,
+    ranges: Vec<Range>,
+    mapping: IntervalMapping,
+}
+
+/// A line of a map, as read.
+struct Range {
+    line: usize,
+    dst: u64,
+    src: u64,
+    len: u64,
+}
+
+/// A problem found in an almanac by `Almanac::validate`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Issue {
+    /// Name of the map, such as `seed-to-soil`, or `seeds`.
+    pub map: String,
+    pub line: usize,
+    pub kind: IssueKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IssueKind {
+    /// The source range overlaps the one of an earlier line.
+    Overlap {
+        line: usize,
+    },
+    ZeroLength,
+    SourceOverflow,
+    DestinationOverflow,
+    /// The last seed has no length when seeds are read as pairs.
+    OddSeedCount,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {} ({}): ", self.line, self.map)?;
+        match self.kind {
+            IssueKind::Overlap { line } => {
+                write!(f, "source range overlaps the one of line {}", line)
+            }
+            IssueKind::ZeroLength => write!(f, "empty range"),
+            IssueKind::SourceOverflow => write!(f, "source range ends past {}", u64::MAX),
+            IssueKind::DestinationOverflow => {
+                write!(f, "destination range ends past {}", u64::MAX)
+            }
+            IssueKind::OddSeedCount => write!(f, "odd number of seeds"),
+        }
+    }
 }
 
 impl Almanac {
     pub fn parse(input: &str) -> Almanac {
+        Almanac::try_parse(input).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_parse(input: &str) -> Result<Almanac, ParseError> {
         let mut a = Almanac {
             seeds_ex1: Vec::new(),
             seed_intervals: Vec::new(),
-            seed2soil: IntervalMapping::new(),
-            soil2fertilizer: IntervalMapping::new(),
-            fertilizer2water: IntervalMapping::new(),
-            water2light: IntervalMapping::new(),
-            light2temperature: IntervalMapping::new(),
-            temperature2humidity: IntervalMapping::new(),
-            humidity2location: IntervalMapping::new(),
+            stages: Vec::new(),
         };
-        let mut lines = input.lines();
-
-        let str_seeds = lines.next().unwrap();
-        let start_seeds = str_seeds.find(|c: char| c.is_digit(10)).unwrap();
-        str_seeds[start_seeds..]
-            .split_whitespace()
-            .for_each(|str_num| a.seeds_ex1.push(str_num.parse().unwrap()));
+        let mut lines = input.lines().enumerate();
+
+        let (_, str_seeds) = lines
+            .next()
+            .ok_or_else(|| ParseError::at_end(input, "'seeds:'"))?;
+        let seeds = str_seeds
+            .strip_prefix("seeds:")
+            .ok_or_else(|| ParseError::at(input, str_seeds, "'seeds:'"))?;
+        for str_num in seeds.split_whitespace() {
+            a.seeds_ex1
+                .push(parse_token(input, str_num, "seed number")?);
+        }
 
         for i in 0..a.seeds_ex1.len() / 2 {
+            let start = a.seeds_ex1[2 * i];
             a.seed_intervals.push(Interval {
-                start: a.seeds_ex1[2 * i],
-                range: a.seeds_ex1[2 * i + 1],
+                start,
+                range: a.seeds_ex1[2 * i + 1].min(u64::MAX - start),
             });
         }
-        a.seed_intervals.sort_by(|x, y| x.start().cmp(&y.start()));
-
-        lines.next();
-        lines.next();
-        parse_mapping(&mut a.seed2soil, &mut lines);
+        a.seed_intervals.sort_by_key(|x| x.start());
 
-        lines.next();
-        parse_mapping(&mut a.soil2fertilizer, &mut lines);
-
-        lines.next();
-        parse_mapping(&mut a.fertilizer2water, &mut lines);
+        while let Some((_, header)) = lines.find(|(_, line)| !line.is_empty()) {
+            a.stages.push(parse_stage(header, input, &mut lines)?);
+        }
 
-        lines.next();
-        parse_mapping(&mut a.water2light, &mut lines);
+        Ok(a)
+    }
 
-        lines.next();
-        parse_mapping(&mut a.light2temperature, &mut lines);
+    /// Returns the problems of the almanac, in order of line: overlapping
+    /// source ranges, empty ranges, ranges ending past `u64::MAX` and an odd
+    /// number of seeds.
+    pub fn validate(&self) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        if self.seeds_ex1.len() % 2 == 1 {
+            issues.push(Issue {
+                map: "seeds".to_string(),
+                line: 1,
+                kind: IssueKind::OddSeedCount,
+            });
+        }
 
-        lines.next();
-        parse_mapping(&mut a.temperature2humidity, &mut lines);
+        for stage in self.stages.iter() {
+            let map = format!("{}-to-{}", stage.from, stage.to);
+            let mut issue = |line, kind| {
+                issues.push(Issue {
+                    map: map.clone(),
+                    line,
+                    kind,
+                })
+            };
+
+            for range in stage.ranges.iter() {
+                if range.len == 0 {
+                    issue(range.line, IssueKind::ZeroLength);
+                }
+                if range.src.checked_add(range.len).is_none() {
+                    issue(range.line, IssueKind::SourceOverflow);
+                }
+                if range.dst.checked_add(range.len).is_none() {
+                    issue(range.line, IssueKind::DestinationOverflow);
+                }
+            }
 
-        lines.next();
-        parse_mapping(&mut a.humidity2location, &mut lines);
+            let mut ranges: Vec<&Range> = stage.ranges.iter().filter(|r| r.len > 0).collect();
+            ranges.sort_by_key(|range| range.src);
+            let mut furthest: Option<&Range> = None;
+            for range in ranges {
+                let end = |range: &Range| range.src.saturating_add(range.len);
+                match furthest {
+                    Some(previous) if range.src < end(previous) => {
+                        // report the later line, overlapping the earlier one
+                        let (first, second) = if previous.line < range.line {
+                            (previous, range)
+                        } else {
+                            (range, previous)
+                        };
+                        issue(second.line, IssueKind::Overlap { line: first.line });
+                        if end(range) > end(previous) {
+                            furthest = Some(range);
+                        }
+                    }
+                    _ => furthest = Some(range),
+                }
+            }
+        }
 
-        a
+        issues.sort_by_key(|issue| issue.line);
+        issues
     }
 
     /// Returns a slice of seeds for exercise 5.1.
     pub fn seeds_ex1(&self) -> &[u64] {
-        &self.seeds_ex1.as_slice()
+        self.seeds_ex1.as_slice()
     }
 
     pub fn seed_intervals(&self) -> &[Interval] {
-        &self.seed_intervals.as_slice()
-    }
-
-    pub fn soil(&self, seed: u64) -> u64 {
-        self.seed2soil.get(seed)
-    }
-
-    pub fn soil_intervals(&self) -> Vec<Interval> {
-        self.seed2soil.intervals(self.seed_intervals.as_slice())
-    }
-
-    pub fn fertilizer(&self, seed: u64) -> u64 {
-        self.soil2fertilizer.get(self.soil(seed))
-    }
-
-    pub fn fertilizer_intervals(&self) -> Vec<Interval> {
-        self.soil2fertilizer
-            .intervals(self.soil_intervals().as_slice())
-    }
-
-    pub fn water(&self, seed: u64) -> u64 {
-        self.fertilizer2water.get(self.fertilizer(seed))
+        self.seed_intervals.as_slice()
     }
 
-    pub fn water_intervals(&self) -> Vec<Interval> {
-        self.fertilizer2water
-            .intervals(self.fertilizer_intervals().as_slice())
+    /// Iterates over the categories, in order of first appearance.
+    pub fn categories(&self) -> impl Iterator<Item = &str> {
+        let mut seen = HashSet::new();
+        self.stages
+            .iter()
+            .flat_map(|stage| [stage.from.as_str(), stage.to.as_str()])
+            .filter(move |category| seen.insert(*category))
     }
 
-    pub fn light(&self, seed: u64) -> u64 {
-        self.water2light.get(self.water(seed))
+    /// Returns the categories crossed from `from` to `to`, both included, or
+    /// `None` if `to` cannot be reached from `from` or `from` is unknown.
+    ///
+    /// When several paths exist, the one with the fewest maps is chosen.
+    pub fn path(&self, from: &str, to: &str) -> Option<Vec<&str>> {
+        let stages = self.stages_between(from, to)?;
+        let mut path = vec![self.categories().find(|category| *category == from)?];
+        path.extend(stages.iter().map(|stage| stage.to.as_str()));
+        Some(path)
     }
 
-    pub fn light_intervals(&self) -> Vec<Interval> {
-        self.water2light
-            .intervals(self.water_intervals().as_slice())
+    /// Converts `value` of category `from` into category `to`.
+    pub fn convert(&self, value: u64, from: &str, to: &str) -> Option<u64> {
+        let stages = self.stages_between(from, to)?;
+        Some(
+            stages
+                .iter()
+                .fold(value, |value, stage| stage.mapping.get(value)),
+        )
     }
 
-    pub fn temperature(&self, seed: u64) -> u64 {
-        self.light2temperature.get(self.light(seed))
+    /// Converts `intervals` of category `from` into category `to`.
+    pub fn convert_intervals(
+        &self,
+        intervals: &[Interval],
+        from: &str,
+        to: &str,
+    ) -> Option<IntervalSet> {
+        let stages = self.stages_between(from, to)?;
+        let mut intervals: IntervalSet = intervals.iter().copied().collect();
+        for stage in stages {
+            intervals = stage.mapping.intervals(&intervals);
+        }
+        Some(intervals)
     }
 
-    pub fn temperature_intervals(&self) -> Vec<Interval> {
-        self.light2temperature
-            .intervals(self.light_intervals().as_slice())
+    /// Returns the single mapping equivalent to the chain of maps from `from`
+    /// to `to`.
+    pub fn mapping(&self, from: &str, to: &str) -> Option<IntervalMapping> {
+        let stages = self.stages_between(from, to)?;
+        Some(
+            stages
+                .iter()
+                .fold(IntervalMapping::new(), |mapping, stage| {
+                    mapping.compose(&stage.mapping)
+                }),
+        )
     }
 
-    pub fn humidity(&self, seed: u64) -> u64 {
-        self.temperature2humidity.get(self.temperature(seed))
+    /// Returns the values of category `from` converted into `value` of
+    /// category `to`, in increasing order.
+    pub fn inverse(&self, value: u64, from: &str, to: &str) -> Option<Vec<u64>> {
+        Some(self.mapping(from, to)?.preimages(value))
     }
 
-    pub fn humidity_intervals(&self) -> Vec<Interval> {
-        self.temperature2humidity
-            .intervals(&self.temperature_intervals().as_slice())
+    /// Returns the intervals of category `from` converted into `intervals` of
+    /// category `to`.
+    pub fn inverse_intervals(
+        &self,
+        intervals: &[Interval],
+        from: &str,
+        to: &str,
+    ) -> Option<IntervalSet> {
+        Some(self.mapping(from, to)?.preimage_intervals(intervals))
     }
 
-    pub fn location(&self, seed: u64) -> u64 {
-        self.humidity2location.get(self.humidity(seed))
-    }
+    /// Breadth-first search of the maps leading from `from` to `to`.
+    fn stages_between(&self, from: &str, to: &str) -> Option<Vec<&Stage>> {
+        if !self.categories().any(|category| category == from) {
+            return None;
+        }
+        let mut reached_by: HashMap<&str, Option<&Stage>> = HashMap::new();
+        reached_by.insert(from, None);
+        let mut queue = VecDeque::from([from]);
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                break;
+            }
+            for stage in self.stages.iter().filter(|stage| stage.from == category) {
+                if !reached_by.contains_key(stage.to.as_str()) {
+                    reached_by.insert(&stage.to, Some(stage));
+                    queue.push_back(&stage.to);
+                }
+            }
+        }
 
-    pub fn location_intervals(&self) -> Vec<Interval> {
-        self.humidity2location
-            .intervals(&self.humidity_intervals().as_slice())
+        let mut stages = Vec::new();
+        let mut category = to;
+        while let Some(stage) = reached_by.get(category)?.as_ref() {
+            stages.push(*stage);
+            category = &stage.from;
+        }
+        stages.reverse();
+        Some(stages)
     }
 }
 
-fn parse_mapping(mapping: &mut IntervalMapping, lines: &mut Lines<'_>) {
-    while let Some(str_mapping) = lines.next() {
+fn parse_stage(
+    header: &str,
+    input: &str,
+    lines: &mut Enumerate<Lines<'_>>,
+) -> Result<Stage, ParseError> {
+    let (from, to) = header
+        .strip_suffix(" map:")
+        .and_then(|categories| categories.split_once("-to-"))
+        .filter(|(from, to)| !from.is_empty() && !to.is_empty())
+        .ok_or_else(|| ParseError::at(input, header, "map header"))?;
+
+    let mut ranges = Vec::new();
+    let mut mapping = IntervalMapping::new();
+    for (index, str_mapping) in lines.by_ref() {
         if str_mapping.is_empty() {
             break;
         }
-        let numbers: Vec<u64> = str_mapping
-            .split_whitespace()
-            .map(|str_num| str_num.parse::<u64>().unwrap())
-            .collect();
+        let mut numbers = [0u64; 3];
+        let mut str_numbers = str_mapping.split_whitespace();
+        for n in numbers.iter_mut() {
+            let str_num = str_numbers.next().ok_or_else(|| {
+                ParseError::at(input, &str_mapping[str_mapping.len()..], "number")
+            })?;
+            *n = parse_token(input, str_num, "number")?;
+        }
+        if let Some(extra) = str_numbers.next() {
+            return Err(ParseError::at(input, extra, "end of line"));
+        }
         mapping.push(numbers[1], numbers[0], numbers[2]);
+        ranges.push(Range {
+            line: index + 1,
+            dst: numbers[0],
+            src: numbers[1],
+            len: numbers[2],
+        });
     }
+
+    Ok(Stage {
+        from: from.to_string(),
+        to: to.to_string(),
+        ranges,
+        mapping,
+    })
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Interval {
     start: u64,
     range: u64,
@@ -174,7 +360,7 @@ impl Interval {
             panic!("invalid");
         }
         Interval {
-            start: start,
+            start,
             range: end - start,
         }
     }
@@ -185,8 +371,139 @@ impl Interval {
     pub fn end(&self) -> u64 {
         self.start + self.range
     }
+
+    pub fn len(&self) -> u64 {
+        self.range
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.range == 0
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        self.start <= value && value < self.end()
+    }
+}
+
+/// Disjoint intervals, sorted by start, with no two intervals touching and
+/// no empty interval.
+///
+/// Collecting intervals into a set merges the ones overlapping or adjacent.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
 }
 
+impl IntervalSet {
+    /// Returns the empty set.
+    pub fn new() -> IntervalSet {
+        IntervalSet {
+            intervals: Vec::new(),
+        }
+    }
+
+    pub fn intervals(&self) -> &[Interval] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Returns the number of values in the set.
+    pub fn length(&self) -> u64 {
+        self.intervals.iter().map(|interval| interval.len()).sum()
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        match self
+            .intervals
+            .partition_point(|interval| interval.start() <= value)
+        {
+            0 => false,
+            i => self.intervals[i - 1].contains(value),
+        }
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        self.intervals
+            .iter()
+            .chain(other.intervals.iter())
+            .copied()
+            .collect()
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let (mut i, mut j) = (0, 0);
+        let mut intersection = Vec::new();
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a, b) = (self.intervals[i], other.intervals[j]);
+            let low = a.start().max(b.start());
+            let high = a.end().min(b.end());
+            if low < high {
+                intersection.push(Interval::new(low, high));
+            }
+            if a.end() < b.end() {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet {
+            intervals: intersection,
+        }
+    }
+
+    /// Returns the values of `self` not in `other`.
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut difference = Vec::new();
+        let mut j = 0;
+        for interval in self.intervals.iter() {
+            while j < other.intervals.len() && other.intervals[j].end() <= interval.start() {
+                j += 1;
+            }
+            let mut start = interval.start();
+            for removed in other.intervals[j..]
+                .iter()
+                .take_while(|removed| removed.start() < interval.end())
+            {
+                if start < removed.start() {
+                    difference.push(Interval::new(start, removed.start()));
+                }
+                start = start.max(removed.end());
+            }
+            if start < interval.end() {
+                difference.push(Interval::new(start, interval.end()));
+            }
+        }
+        IntervalSet {
+            intervals: difference,
+        }
+    }
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Interval>>(iter: I) -> IntervalSet {
+        let mut intervals: Vec<Interval> = iter
+            .into_iter()
+            .filter(|interval| !interval.is_empty())
+            .collect();
+        intervals.sort_by_key(|interval| interval.start());
+
+        let mut merged: Vec<Interval> = Vec::new();
+        for interval in intervals {
+            match merged.last_mut() {
+                Some(last) if interval.start() <= last.end() => {
+                    last.range = last.range.max(interval.end() - last.start)
+                }
+                _ => merged.push(interval),
+            }
+        }
+        IntervalSet { intervals: merged }
+    }
+}
+
+#[derive(Clone, Copy)]
 struct SingleIntervalMapping {
     src: u64,
     dst: u64,
@@ -195,7 +512,7 @@ struct SingleIntervalMapping {
 
 impl SingleIntervalMapping {
     fn get(&self, key: u64) -> Option<u64> {
-        if key >= self.src && key < self.src + self.range {
+        if key >= self.src && key - self.src < self.range {
             return Some(key - self.src + self.dst);
         }
         None
@@ -218,46 +535,178 @@ impl SingleIntervalMapping {
     }
 }
 
-struct IntervalMapping {
-    intervals: Vec<SingleIntervalMapping>,
+/// Ranges of values mapped to other ranges, values outside of any range
+/// being mapped to themselves.
+///
+/// When source ranges overlap, a key is mapped by the range starting first,
+/// or pushed first if they start together. Parts of ranges ending past
+/// `u64::MAX` are ignored.
+pub struct IntervalMapping {
+    /// Ranges in the order they were pushed.
+    ranges: Vec<SingleIntervalMapping>,
+    /// Disjoint ranges used for lookups, sorted by source start, built on
+    /// first use after a push.
+    intervals: OnceCell<Vec<SingleIntervalMapping>>,
 }
 
 impl IntervalMapping {
-    fn new() -> IntervalMapping {
+    /// Returns the identity mapping.
+    pub fn new() -> IntervalMapping {
         IntervalMapping {
-            intervals: Vec::new(),
+            ranges: Vec::new(),
+            intervals: OnceCell::new(),
         }
     }
 
-    fn push(&mut self, src: u64, dst: u64, range: u64) {
-        self.intervals
-            .push(SingleIntervalMapping { src, dst, range });
-        self.intervals.sort_by(|x, y| x.src.cmp(&y.src));
+    /// Maps the `range` values from `src` to the ones from `dst`.
+    pub fn push(&mut self, src: u64, dst: u64, range: u64) {
+        self.ranges.push(SingleIntervalMapping { src, dst, range });
+        self.intervals.take();
+    }
+
+    pub fn get(&self, key: u64) -> u64 {
+        let intervals = self.table();
+        match intervals.partition_point(|mapping| mapping.src <= key) {
+            0 => key,
+            i => intervals[i - 1].get(key).unwrap_or(key),
+        }
+    }
+
+    /// Returns the disjoint ranges used for lookups, each range keeping the
+    /// keys not taken by the ones before it.
+    fn table(&self) -> &[SingleIntervalMapping] {
+        self.intervals.get_or_init(|| {
+            let mut ranges = self.ranges.clone();
+            ranges.sort_by_key(|mapping| mapping.src);
+
+            let mut intervals = Vec::with_capacity(ranges.len());
+            let mut cursor = 0;
+            for mapping in ranges.iter() {
+                let start = mapping.src.max(cursor);
+                let range = mapping
+                    .range
+                    .min(u64::MAX - mapping.src)
+                    .min(u64::MAX - mapping.dst);
+                let end = mapping.src + range;
+                if start < end {
+                    intervals.push(SingleIntervalMapping {
+                        src: start,
+                        dst: mapping.dst + (start - mapping.src),
+                        range: end - start,
+                    });
+                    cursor = end;
+                }
+            }
+            intervals
+        })
+    }
+
+    /// Returns the mapping applying `self`, then `then`.
+    pub fn compose(&self, then: &IntervalMapping) -> IntervalMapping {
+        let then = then.pieces();
+        let mut intervals: Vec<SingleIntervalMapping> = Vec::new();
+        for piece in self.pieces() {
+            let (start, end) = (piece.dst_start(), piece.dst_end());
+            let first = then.partition_point(|next| next.src_end() <= start);
+            for next in then[first..].iter().take_while(|next| next.src < end) {
+                let low = start.max(next.src_start());
+                let high = end.min(next.src_end());
+                let composed = SingleIntervalMapping {
+                    src: piece.src + (low - start),
+                    dst: next.dst + (low - next.src),
+                    range: high - low,
+                };
+                match intervals.last_mut() {
+                    Some(last)
+                        if last.src_end() == composed.src && last.dst_end() == composed.dst =>
+                    {
+                        last.range += composed.range
+                    }
+                    _ => intervals.push(composed),
+                }
+            }
+        }
+        intervals.retain(|mapping| mapping.src != mapping.dst);
+
+        IntervalMapping {
+            ranges: intervals.clone(),
+            intervals: OnceCell::from(intervals),
+        }
+    }
+
+    /// Returns the keys mapped to `value`, in increasing order.
+    pub fn preimages(&self, value: u64) -> Vec<u64> {
+        match value.checked_add(1) {
+            Some(end) => self
+                .preimage_intervals(&[Interval::new(value, end)])
+                .intervals()
+                .iter()
+                .flat_map(|interval| interval.start()..interval.end())
+                .collect(),
+            // ranges end at u64::MAX at most, so nothing else reaches it
+            None => vec![value],
+        }
     }
 
-    fn get(&self, key: u64) -> u64 {
-        for mapping in self.intervals.iter() {
-            if let Some(value) = mapping.get(key) {
-                return value;
+    /// Returns the keys mapped into `intervals`, unmapped keys included.
+    pub fn preimage_intervals(&self, intervals: &[Interval]) -> IntervalSet {
+        let mut preimages: Vec<Interval> = Vec::new();
+        for piece in self.pieces() {
+            for interval in intervals {
+                let low = interval.start().max(piece.dst_start());
+                let high = interval.end().min(piece.dst_end());
+                if low < high {
+                    preimages.push(Interval::new(
+                        low - piece.dst + piece.src,
+                        high - piece.dst + piece.src,
+                    ));
+                }
             }
         }
-        key
+        preimages.into_iter().collect()
     }
 
-    fn intervals(&self, intervals: &[Interval]) -> Vec<Interval> {
+    /// Returns the ranges covering every value but `u64::MAX`, the ones
+    /// mapped to themselves included.
+    fn pieces(&self) -> Vec<SingleIntervalMapping> {
+        let mut pieces = Vec::new();
+        let mut cursor = 0;
+        for mapping in self.table() {
+            if cursor < mapping.src {
+                pieces.push(SingleIntervalMapping {
+                    src: cursor,
+                    dst: cursor,
+                    range: mapping.src - cursor,
+                });
+            }
+            pieces.push(*mapping);
+            cursor = mapping.src_end();
+        }
+        if cursor < u64::MAX {
+            pieces.push(SingleIntervalMapping {
+                src: cursor,
+                dst: cursor,
+                range: u64::MAX - cursor,
+            });
+        }
+        pieces
+    }
+
+    fn intervals(&self, intervals: &IntervalSet) -> IntervalSet {
+        let table = self.table();
         let mut v: Vec<Interval> = Vec::new();
 
         let mut interval_mapping_cursor: usize = 0;
 
-        'intervals: for interval in intervals {
+        'intervals: for interval in intervals.intervals() {
             let mut start = interval.start();
             let end = interval.end();
 
             loop {
-                if self.intervals.len() <= interval_mapping_cursor {
+                if table.len() <= interval_mapping_cursor {
                     break;
                 }
-                let interval_mapping = &self.intervals[interval_mapping_cursor];
+                let interval_mapping = &table[interval_mapping_cursor];
 
                 if start < interval_mapping.src_start() {
                     if end <= interval_mapping.src_start() {
@@ -299,14 +748,33 @@ impl IntervalMapping {
             v.push(Interval::new(start, end));
         }
 
-        v.sort_by(|x, y| x.start().cmp(&y.start()));
-        v
+        v.into_iter().collect()
+    }
+}
+
+impl Default for IntervalMapping {
+    fn default() -> IntervalMapping {
+        IntervalMapping::new()
+    }
+}
+
+/// One range per line, as in the almanac: destination, source and length.
+impl fmt::Display for IntervalMapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, mapping) in self.table().iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{} {} {}", mapping.dst, mapping.src, mapping.range)?;
+        }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::Found;
 
     const INPUT: &str = "seeds: 79 14 55 13
 
@@ -369,19 +837,23 @@ humidity-to-location map:
     #[test]
     fn test_soil() {
         let a = Almanac::parse(&INPUT);
-        assert_eq!(a.soil(98), 50);
-        assert_eq!(a.soil(99), 51);
-        assert_eq!(a.soil(50), 52);
-        assert_eq!(a.soil(97), 99);
-        assert_eq!(a.soil(0), 0);
-        assert_eq!(a.soil(49), 49);
-        assert_eq!(a.soil(100), 100);
+        assert_eq!(a.convert(98, "seed", "soil").unwrap(), 50);
+        assert_eq!(a.convert(99, "seed", "soil").unwrap(), 51);
+        assert_eq!(a.convert(50, "seed", "soil").unwrap(), 52);
+        assert_eq!(a.convert(97, "seed", "soil").unwrap(), 99);
+        assert_eq!(a.convert(0, "seed", "soil").unwrap(), 0);
+        assert_eq!(a.convert(49, "seed", "soil").unwrap(), 49);
+        assert_eq!(a.convert(100, "seed", "soil").unwrap(), 100);
     }
 
     #[test]
     fn test_soil_intervals() {
         let a = Almanac::parse(&INPUT);
-        let intervals = a.soil_intervals();
+        let intervals = a
+            .convert_intervals(a.seed_intervals(), "seed", "soil")
+            .unwrap()
+            .intervals()
+            .to_vec();
 
         assert_eq!(intervals.len(), 2);
 
@@ -395,16 +867,20 @@ humidity-to-location map:
     #[test]
     fn test_fertilizer() {
         let a = Almanac::parse(&INPUT);
-        assert_eq!(a.fertilizer(79), 81);
-        assert_eq!(a.fertilizer(14), 53);
-        assert_eq!(a.fertilizer(55), 57);
-        assert_eq!(a.fertilizer(13), 52);
+        assert_eq!(a.convert(79, "seed", "fertilizer").unwrap(), 81);
+        assert_eq!(a.convert(14, "seed", "fertilizer").unwrap(), 53);
+        assert_eq!(a.convert(55, "seed", "fertilizer").unwrap(), 57);
+        assert_eq!(a.convert(13, "seed", "fertilizer").unwrap(), 52);
     }
 
     #[test]
     fn test_fertilizer_intervals() {
         let a = Almanac::parse(&INPUT);
-        let intervals = a.fertilizer_intervals();
+        let intervals = a
+            .convert_intervals(a.seed_intervals(), "seed", "fertilizer")
+            .unwrap()
+            .intervals()
+            .to_vec();
 
         assert_eq!(intervals.len(), 2);
 
@@ -418,16 +894,20 @@ humidity-to-location map:
     #[test]
     fn test_water() {
         let a = Almanac::parse(&INPUT);
-        assert_eq!(a.water(79), 81);
-        assert_eq!(a.water(14), 49);
-        assert_eq!(a.water(55), 53);
-        assert_eq!(a.water(13), 41);
+        assert_eq!(a.convert(79, "seed", "water").unwrap(), 81);
+        assert_eq!(a.convert(14, "seed", "water").unwrap(), 49);
+        assert_eq!(a.convert(55, "seed", "water").unwrap(), 53);
+        assert_eq!(a.convert(13, "seed", "water").unwrap(), 41);
     }
 
     #[test]
     fn test_water_intervals() {
         let a = Almanac::parse(&INPUT);
-        let intervals = a.water_intervals();
+        let intervals = a
+            .convert_intervals(a.seed_intervals(), "seed", "water")
+            .unwrap()
+            .intervals()
+            .to_vec();
 
         assert_eq!(intervals.len(), 3);
 
@@ -444,43 +924,394 @@ humidity-to-location map:
     #[test]
     fn test_light() {
         let a = Almanac::parse(&INPUT);
-        assert_eq!(a.light(79), 74);
-        assert_eq!(a.light(14), 42);
-        assert_eq!(a.light(55), 46);
-        assert_eq!(a.light(13), 34);
+        assert_eq!(a.convert(79, "seed", "light").unwrap(), 74);
+        assert_eq!(a.convert(14, "seed", "light").unwrap(), 42);
+        assert_eq!(a.convert(55, "seed", "light").unwrap(), 46);
+        assert_eq!(a.convert(13, "seed", "light").unwrap(), 34);
     }
 
     #[test]
     fn test_temperature() {
         let a = Almanac::parse(&INPUT);
-        assert_eq!(a.temperature(79), 78);
-        assert_eq!(a.temperature(14), 42);
-        assert_eq!(a.temperature(55), 82);
-        assert_eq!(a.temperature(13), 34);
+        assert_eq!(a.convert(79, "seed", "temperature").unwrap(), 78);
+        assert_eq!(a.convert(14, "seed", "temperature").unwrap(), 42);
+        assert_eq!(a.convert(55, "seed", "temperature").unwrap(), 82);
+        assert_eq!(a.convert(13, "seed", "temperature").unwrap(), 34);
     }
 
     #[test]
     fn test_humidity() {
         let a = Almanac::parse(&INPUT);
-        assert_eq!(a.humidity(79), 78);
-        assert_eq!(a.humidity(14), 43);
-        assert_eq!(a.humidity(55), 82);
-        assert_eq!(a.humidity(13), 35);
+        assert_eq!(a.convert(79, "seed", "humidity").unwrap(), 78);
+        assert_eq!(a.convert(14, "seed", "humidity").unwrap(), 43);
+        assert_eq!(a.convert(55, "seed", "humidity").unwrap(), 82);
+        assert_eq!(a.convert(13, "seed", "humidity").unwrap(), 35);
     }
 
     #[test]
     fn test_location() {
         let a = Almanac::parse(&INPUT);
-        assert_eq!(a.location(79), 82);
-        assert_eq!(a.location(14), 43);
-        assert_eq!(a.location(55), 86);
-        assert_eq!(a.location(13), 35);
+        assert_eq!(a.convert(79, "seed", "location").unwrap(), 82);
+        assert_eq!(a.convert(14, "seed", "location").unwrap(), 43);
+        assert_eq!(a.convert(55, "seed", "location").unwrap(), 86);
+        assert_eq!(a.convert(13, "seed", "location").unwrap(), 35);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let e = Almanac::try_parse("seeds: 79 14 5x 13").err().unwrap();
+        assert_eq!((e.line(), e.column()), (1, 14));
+
+        let e = Almanac::try_parse(&INPUT.replace("52 50 48", "52 50"))
+            .err()
+            .unwrap();
+        assert_eq!((e.line(), e.column()), (5, 6));
+        assert_eq!(e.expected(), "number");
+
+        let e = Almanac::try_parse(&INPUT.replace("water-to-light map:", "water-light map:"))
+            .err()
+            .unwrap();
+        assert_eq!((e.line(), e.column()), (18, 1));
+        assert_eq!(e.expected(), "map header");
+
+        let e = Almanac::try_parse("seeds: 1\n\nseed-to-soil map:\n1 2")
+            .err()
+            .unwrap();
+        assert_eq!((e.line(), e.column()), (4, 4));
+        assert_eq!(e.found(), &Found::EndOfInput);
+    }
+
+    #[test]
+    fn test_categories() {
+        let a = Almanac::parse(&INPUT);
+        assert_eq!(
+            a.categories().collect::<Vec<&str>>(),
+            vec![
+                "seed",
+                "soil",
+                "fertilizer",
+                "water",
+                "light",
+                "temperature",
+                "humidity",
+                "location"
+            ]
+        );
+        assert_eq!(
+            a.path("water", "temperature"),
+            Some(vec!["water", "light", "temperature"])
+        );
+        assert_eq!(a.path("soil", "soil"), Some(vec!["soil"]));
+        assert_eq!(a.path("location", "seed"), None);
+        assert_eq!(a.path("unknown", "unknown"), None);
+    }
+
+    #[test]
+    fn test_convert() {
+        let a = Almanac::parse(&INPUT);
+        assert_eq!(a.convert(14, "seed", "humidity"), Some(43));
+        assert_eq!(a.convert(53, "fertilizer", "light"), Some(42));
+        assert_eq!(a.convert(7, "water", "water"), Some(7));
+        assert_eq!(a.convert(82, "location", "seed"), None);
+        assert_eq!(a.convert(82, "seed", "unknown"), None);
+        assert_eq!(
+            a.convert_intervals(&[Interval::new(79, 93)], "seed", "location")
+                .map(|set| set.intervals().to_vec()),
+            Some(vec![
+                Interval::new(46, 56),
+                Interval::new(60, 61),
+                Interval::new(82, 85)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_compose() {
+        let a = Almanac::parse(&INPUT);
+        let mapping = a.mapping("seed", "location").unwrap();
+        for seed in 0..200 {
+            assert_eq!(
+                mapping.get(seed),
+                a.convert(seed, "seed", "location").unwrap()
+            );
+        }
+
+        let mut first = IntervalMapping::new();
+        first.push(0, 10, 5);
+        let mut second = IntervalMapping::new();
+        second.push(12, 0, 10);
+        assert_eq!(first.compose(&second).to_string(), "10 0 2\n0 2 3\n0 12 10");
+        assert_eq!(first.compose(&IntervalMapping::new()).to_string(), "10 0 5");
+        // swapping two ranges twice gives the identity
+        let mut swap = IntervalMapping::new();
+        swap.push(0, 10, 5);
+        swap.push(10, 0, 5);
+        assert_eq!(swap.compose(&swap).to_string(), "");
+        assert_eq!(a.mapping("soil", "soil").unwrap().to_string(), "");
+    }
+
+    #[test]
+    fn test_overlapping_ranges() {
+        let ranges = [(0, 1000, 100), (10, 0, 10), (10, 500, 200), (150, 300, 20)];
+        let mut mapping = IntervalMapping::new();
+        for (src, dst, range) in ranges {
+            mapping.push(src, dst, range);
+        }
+        // the first range containing a key, by source start, maps it
+        let first_match = |key: u64| {
+            ranges
+                .iter()
+                .find(|(src, _, range)| *src <= key && key < src + range)
+                .map_or(key, |(src, dst, _)| key - src + dst)
+        };
+        for key in 0..300 {
+            assert_eq!(mapping.get(key), first_match(key), "key {}", key);
+        }
+        assert_eq!(mapping.get(50), 1050);
+        assert_eq!(mapping.get(150), 640);
+
+        let a = Almanac::parse("seeds: 50 1\n\nseed-to-soil map:\n1000 0 100\n0 10 10");
+        assert_eq!(a.convert(50, "seed", "soil"), Some(1050));
+        assert_eq!(a.convert(15, "seed", "soil"), Some(1015));
+        assert_eq!(
+            a.convert_intervals(&[Interval::new(5, 15)], "seed", "soil")
+                .unwrap()
+                .intervals(),
+            &[Interval::new(1005, 1015)]
+        );
+        assert_eq!(a.inverse(1015, "seed", "soil"), Some(vec![15, 1015]));
+    }
+
+    #[test]
+    fn test_preimages_at_u64_max() {
+        let mut mapping = IntervalMapping::new();
+        mapping.push(0, u64::MAX - 10, 10);
+        assert_eq!(mapping.get(u64::MAX), u64::MAX);
+        assert_eq!(mapping.preimages(u64::MAX), vec![u64::MAX]);
+        assert_eq!(mapping.preimages(u64::MAX - 1), vec![9, u64::MAX - 1]);
+        assert_eq!(mapping.preimages(5), vec![]);
+        assert_eq!(
+            mapping
+                .preimage_intervals(&[Interval::new(u64::MAX - 3, u64::MAX)])
+                .intervals(),
+            &[Interval::new(7, 10), Interval::new(u64::MAX - 3, u64::MAX)]
+        );
+    }
+
+    #[test]
+    fn test_inverse() {
+        let a = Almanac::parse(&INPUT);
+        assert_eq!(a.inverse(50, "seed", "soil"), Some(vec![98]));
+        assert_eq!(a.inverse(99, "seed", "soil"), Some(vec![97]));
+        assert_eq!(a.inverse(10, "seed", "soil"), Some(vec![10]));
+        assert_eq!(a.inverse(100, "seed", "soil"), Some(vec![100]));
+        assert_eq!(a.inverse(46, "location", "seed"), None);
+
+        // the seed picked by min_location_ex2
+        let seeds = a.inverse(46, "seed", "location").unwrap();
+        assert!(seeds.contains(&82));
+        for seed in 0..200 {
+            assert_eq!(
+                seeds.contains(&seed),
+                a.convert(seed, "seed", "location") == Some(46)
+            );
+        }
+
+        let locations = [Interval::new(40, 60), Interval::new(90, 100)];
+        let seeds = a.inverse_intervals(&locations, "seed", "location").unwrap();
+        for seed in 0..200 {
+            let location = a.convert(seed, "seed", "location").unwrap();
+            assert_eq!(
+                seeds.contains(seed),
+                locations
+                    .iter()
+                    .any(|x| x.start() <= location && location < x.end())
+            );
+        }
+        assert!(seeds
+            .intervals()
+            .windows(2)
+            .all(|w| w[0].end() < w[1].start()));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(Almanac::parse(&INPUT).validate(), vec![]);
+
+        let a = Almanac::parse(
+            "seeds: 79 14 55
+
+seed-to-soil map:
+50 98 2
+52 50 48
+10 60 5
+0 0 0
+
+soil-to-fertilizer map:
+0 18446744073709551610 6
+18446744073709551615 0 7",
+        );
+        let issues: Vec<String> = a.validate().iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            issues,
+            vec![
+                "line 1 (seeds): odd number of seeds",
+                "line 6 (seed-to-soil): source range overlaps the one of line 5",
+                "line 7 (seed-to-soil): empty range",
+                "line 10 (soil-to-fertilizer): source range ends past 18446744073709551615",
+                "line 11 (soil-to-fertilizer): destination range ends past 18446744073709551615",
+            ]
+        );
+        assert_eq!(
+            a.validate()[1],
+            Issue {
+                map: "seed-to-soil".to_string(),
+                line: 6,
+                kind: IssueKind::Overlap { line: 5 },
+            }
+        );
+    }
+
+    #[test]
+    fn test_overflowing_ranges() {
+        let a = Almanac::parse(
+            "seeds: 18446744073709551610 10
+
+seed-to-soil map:
+0 18446744073709551610 10
+18446744073709551610 0 10",
+        );
+        assert_eq!(a.validate().len(), 2);
+        // only the parts of the ranges up to u64::MAX are mapped
+        assert_eq!(a.convert(18446744073709551612, "seed", "soil"), Some(2));
+        assert_eq!(a.convert(3, "seed", "soil"), Some(18446744073709551613));
+        assert_eq!(a.convert(7, "seed", "soil"), Some(7));
+        assert_eq!(a.convert(u64::MAX, "seed", "soil"), Some(u64::MAX));
+        assert_eq!(
+            a.convert_intervals(a.seed_intervals(), "seed", "soil")
+                .unwrap()
+                .intervals(),
+            &[Interval::new(0, 5)]
+        );
+        assert_eq!(
+            a.mapping("seed", "soil").unwrap().to_string(),
+            "18446744073709551610 0 5\n0 18446744073709551610 5"
+        );
+    }
+
+    #[test]
+    fn test_interval_set() {
+        let set: IntervalSet = [
+            Interval::new(10, 20),
+            Interval::new(0, 5),
+            Interval::new(15, 25),
+            Interval::new(5, 7),
+            Interval::new(30, 30),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            set.intervals(),
+            &[Interval::new(0, 7), Interval::new(10, 25)]
+        );
+        assert_eq!(set.length(), 22);
+        assert!(set.contains(0) && set.contains(6) && set.contains(24));
+        assert!(!set.contains(7) && !set.contains(9) && !set.contains(25));
+        assert!(IntervalSet::new().is_empty());
+
+        let other: IntervalSet = [Interval::new(3, 12), Interval::new(20, 40)]
+            .into_iter()
+            .collect();
+        assert_eq!(set.union(&other).intervals(), &[Interval::new(0, 40)]);
+        assert_eq!(
+            set.intersection(&other).intervals(),
+            &[
+                Interval::new(3, 7),
+                Interval::new(10, 12),
+                Interval::new(20, 25)
+            ]
+        );
+        assert_eq!(
+            set.difference(&other).intervals(),
+            &[Interval::new(0, 3), Interval::new(12, 20)]
+        );
+        assert_eq!(
+            other.difference(&set).intervals(),
+            &[Interval::new(7, 10), Interval::new(25, 40)]
+        );
+        assert!(set.difference(&set).is_empty());
+        assert_eq!(set.intersection(&IntervalSet::new()), IntervalSet::new());
+    }
+
+    #[test]
+    fn test_convert_intervals_stay_merged() {
+        let a = Almanac::parse(&INPUT);
+        // overlapping seed intervals are merged before conversion
+        let seeds = [
+            Interval::new(79, 93),
+            Interval::new(80, 85),
+            Interval::new(55, 68),
+        ];
+        let locations = a.convert_intervals(&seeds, "seed", "location").unwrap();
+        assert_eq!(
+            locations.length(),
+            a.convert_intervals(a.seed_intervals(), "seed", "location")
+                .unwrap()
+                .length()
+        );
+        assert_eq!(locations.length(), 27);
+    }
+
+    #[test]
+    fn test_any_chain() {
+        let a = Almanac::parse(
+            "seeds: 3 2
+
+a-to-b map:
+10 0 5
+
+b-to-c map:
+0 10 2
+
+a-to-c map:
+1 0 100",
+        );
+        // the direct map is shorter than going through b
+        assert_eq!(a.path("a", "c"), Some(vec!["a", "c"]));
+        assert_eq!(a.convert(3, "a", "c"), Some(4));
+        assert_eq!(a.convert(1, "a", "b"), Some(11));
+        assert_eq!(a.convert(11, "b", "c"), Some(1));
+        assert_eq!(
+            a.convert_intervals(&[Interval::new(3, 7)], "a", "b")
+                .map(|set| set.intervals().to_vec()),
+            Some(vec![Interval::new(5, 7), Interval::new(13, 15)])
+        );
     }
 
     #[test]
     fn test_min_location() {
         let a = Almanac::parse(&INPUT);
-        assert_eq!(min_location_ex1(&a), 35);
-        assert_eq!(min_location_ex2(&a), 46);
+        assert_eq!(min_location_ex1(&a), Some(35));
+        assert_eq!(min_location_ex2(&a), Some(46));
+    }
+
+    #[test]
+    fn test_min_location_missing() {
+        let maps = "seed-to-soil map:\n1 0 5\n\nsoil-to-location map:\n0 1 5";
+        let a = Almanac::parse(&format!("seeds: 3 2\n\n{}", maps));
+        assert_eq!(min_location_ex1(&a), Some(2));
+        assert_eq!(min_location_ex2(&a), Some(3));
+
+        let a = Almanac::parse(&format!("seeds:\n\n{}", maps));
+        assert_eq!(min_location_ex1(&a), None);
+        assert_eq!(min_location_ex2(&a), None);
+
+        let a = Almanac::parse(&format!("seeds: 3 2 7\n\n{}", maps));
+        assert_eq!(min_location_ex1(&a), Some(2));
+        assert_eq!(min_location_ex2(&a), None);
+
+        let a = Almanac::parse("seeds: 3 2\n\nseed-to-soil map:\n1 0 5");
+        assert_eq!(min_location_ex1(&a), None);
+        assert_eq!(min_location_ex2(&a), None);
     }
 }
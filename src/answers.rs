@@ -0,0 +1,131 @@
+use crate::error::{parse_token, ParseError};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Expected answers, by day and part.
+///
+/// The text format has one answer per line, `<day> <part> <answer>`; empty
+/// lines and lines starting with `#` are ignored.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Answers {
+    answers: BTreeMap<(u32, u32), String>,
+}
+
+/// Outcome of checking a computed answer against the expected one.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Fail(String),
+    Unknown,
+}
+
+impl Answers {
+    pub fn new() -> Answers {
+        Answers::default()
+    }
+
+    pub fn parse(input: &str) -> Answers {
+        Answers::try_parse(input).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_parse(input: &str) -> Result<Answers, ParseError> {
+        let mut answers = Answers::new();
+
+        for line in input.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let day = parts.next().unwrap();
+            let day: u32 = parse_token(input, day, "day")?;
+            let part = parts
+                .next()
+                .ok_or_else(|| ParseError::at(input, &line[line.len()..], "part"))?;
+            if part != "1" && part != "2" {
+                return Err(ParseError::at(input, part, "'1' or '2'"));
+            }
+            let answer = parts
+                .next()
+                .ok_or_else(|| ParseError::at(input, &line[line.len()..], "answer"))?;
+            if let Some(extra) = parts.next() {
+                return Err(ParseError::at(input, extra, "end of line"));
+            }
+
+            answers.record(day, part.parse().unwrap(), answer);
+        }
+
+        Ok(answers)
+    }
+
+    pub fn get(&self, day: u32, part: u32) -> Option<&str> {
+        self.answers.get(&(day, part)).map(|answer| answer.as_str())
+    }
+
+    /// Records an answer, replacing the previous one if any.
+    pub fn record(&mut self, day: u32, part: u32, answer: &str) {
+        self.answers.insert((day, part), answer.to_string());
+    }
+
+    pub fn verify(&self, day: u32, part: u32, answer: &str) -> Verdict {
+        match self.get(day, part) {
+            None => Verdict::Unknown,
+            Some(expected) if expected == answer => Verdict::Pass,
+            Some(expected) => Verdict::Fail(expected.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# day part answer")?;
+        for ((day, part), answer) in &self.answers {
+            writeln!(f, "{} {} {}", day, part, answer)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "# day part answer
+1 2 54019
+
+6 1 393120
+6 2 36872656";
+
+    #[test]
+    fn test_parse() {
+        let a = Answers::parse(INPUT);
+        assert_eq!(a.get(1, 2), Some("54019"));
+        assert_eq!(a.get(1, 1), None);
+        assert_eq!(a.get(6, 2), Some("36872656"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let e = Answers::try_parse("1 2 54019\n6 3 393120").unwrap_err();
+        assert_eq!((e.line(), e.column()), (2, 3));
+
+        let e = Answers::try_parse("1 2").unwrap_err();
+        assert_eq!(e.expected(), "answer");
+
+        let e = Answers::try_parse("x 2 54019").unwrap_err();
+        assert_eq!(e.expected(), "day");
+    }
+
+    #[test]
+    fn test_record_and_verify() {
+        let mut a = Answers::parse(INPUT);
+        assert_eq!(a.verify(6, 1, "393120"), Verdict::Pass);
+        assert_eq!(a.verify(6, 1, "42"), Verdict::Fail("393120".to_string()));
+        assert_eq!(a.verify(2, 1, "2600"), Verdict::Unknown);
+
+        a.record(2, 1, "2600");
+        assert_eq!(a.verify(2, 1, "2600"), Verdict::Pass);
+        assert_eq!(Answers::parse(&a.to_string()), a);
+    }
+}
@@ -1,57 +1,40 @@
+use crate::error::{Found, ParseError};
 use crate::grid2d::Direction;
+use crate::grid2d::Grid;
 use crate::grid2d::Position;
 use std::collections::HashSet;
 use std::fmt;
 
 pub struct EnergyMap {
-    content: Vec<Vec<bool>>,
-    num_rows: usize,
-    num_cols: usize,
+    content: Grid<bool>,
 }
 
 impl EnergyMap {
     fn new(num_rows: usize, num_cols: usize) -> EnergyMap {
         EnergyMap {
-            content: vec![vec![false; num_cols]; num_rows],
-            num_rows,
-            num_cols,
+            content: Grid::new(num_rows, num_cols, false),
         }
     }
     pub fn num_energized(&self) -> u64 {
-        let mut count = 0u64;
-
-        for i in 0..self.num_rows {
-            for j in 0..self.num_cols {
-                if self.content[i][j] {
-                    count += 1;
-                }
-            }
-        }
-
-        count
+        self.content
+            .iter()
+            .filter(|(_, energized)| **energized)
+            .count() as u64
     }
 
     fn energize(&mut self, p: &Position) {
-        self.content[p.row()][p.col()] = true;
+        self.content[*p] = true;
     }
 }
 
 impl fmt::Display for EnergyMap {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.num_rows == 0 {
-            return write!(f, "");
-        }
-
-        let mut s = String::with_capacity(self.num_rows * (self.num_cols + 1) - 1);
-        for i in 0..self.num_rows {
-            for j in 0..self.num_cols {
-                s.push(if self.content[i][j] { '#' } else { '.' });
-            }
-            if i < self.num_rows - 1 {
-                s.push('\n');
-            }
-        }
-        write!(f, "{}", s)
+        write!(
+            f,
+            "{}",
+            self.content
+                .render(|energized| if *energized { '#' } else { '.' })
+        )
     }
 }
 
@@ -64,7 +47,7 @@ enum Device {
 }
 
 impl TryFrom<char> for Device {
-    type Error = &'static str;
+    type Error = ParseError;
 
     fn try_from(c: char) -> Result<Self, Self::Error> {
         match c {
@@ -72,14 +55,19 @@ impl TryFrom<char> for Device {
             '/' => Ok(Device::NorthEastMirror),
             '-' => Ok(Device::HorizontalSplitter),
             '|' => Ok(Device::VerticalSplitter),
-            _ => Err("invalid device character"),
+            _ => Err(ParseError::new(
+                1,
+                1,
+                "'\\', '/', '-' or '|'",
+                Found::Token(c.to_string()),
+            )),
         }
     }
 }
 
-impl Into<char> for &Device {
-    fn into(self) -> char {
-        match self {
+impl From<&Device> for char {
+    fn from(device: &Device) -> char {
+        match device {
             Device::NorthWestMirror => '\\',
             Device::NorthEastMirror => '/',
             Device::HorizontalSplitter => '-',
@@ -89,62 +77,40 @@ impl Into<char> for &Device {
 }
 
 pub struct Contraption {
-    content: Vec<Vec<Option<Device>>>,
-    num_rows: usize,
-    num_cols: usize,
+    content: Grid<Option<Device>>,
 }
 
 impl Contraption {
     pub fn parse(input: &str) -> Contraption {
-        let mut c = Contraption {
-            content: Vec::new(),
-            num_rows: 0,
-            num_cols: 0,
-        };
-
-        let mut is_first_line: bool = true;
-        for line in input.lines() {
-            if is_first_line {
-                let mut row: Vec<Option<Device>> = Vec::new();
-                for c in line.chars() {
-                    row.push(match Device::try_from(c) {
-                        Ok(device) => Some(device),
-                        Err(_) => None,
-                    });
-                }
-                c.num_cols = row.len();
-                c.content.push(row);
-                is_first_line = false;
-                continue;
-            }
+        Contraption::try_parse(input).unwrap_or_else(|e| panic!("{}", e))
+    }
 
-            let mut row: Vec<Option<Device>> = Vec::with_capacity(c.num_cols);
-            for c in line.chars() {
-                row.push(match Device::try_from(c) {
-                    Ok(device) => Some(device),
-                    Err(_) => None,
-                });
-            }
-            c.content.push(row);
-        }
-        c.num_rows = c.content.len();
+    pub fn try_parse(input: &str) -> Result<Contraption, ParseError> {
+        let content = Grid::try_parse_with(input, |c| match c {
+            '.' => Ok(None),
+            _ => Device::try_from(c).map(Some).map_err(|_| {
+                ParseError::new(
+                    1,
+                    1,
+                    "'.', '\\', '/', '-' or '|'",
+                    Found::Token(c.to_string()),
+                )
+            }),
+        })?;
 
-        c
+        Ok(Contraption { content })
     }
 
     fn get(&self, p: &Position) -> Option<Device> {
-        self.content[p.row()][p.col()]
+        self.content[*p]
     }
 
     pub fn trace_beam(&self) -> EnergyMap {
-        self.trace_beam_from(
-            Position::new(0, 0, (self.num_rows, self.num_cols)),
-            Direction::East,
-        )
+        self.trace_beam_from(self.content.position(0, 0), Direction::East)
     }
 
     fn trace_beam_from(&self, pos: Position, incr: Direction) -> EnergyMap {
-        let mut m = EnergyMap::new(self.num_rows, self.num_cols);
+        let mut m = EnergyMap::new(self.content.num_rows(), self.content.num_cols());
 
         let mut visited: HashSet<(usize, usize, Direction)> = HashSet::new();
         self.rec_trace_beam(&mut m, &mut visited, pos, incr);
@@ -187,19 +153,17 @@ impl Contraption {
                         }
                     }
                     Device::NorthWestMirror => {
-                        increment = match increment {
-                            Direction::East => Direction::South,
-                            Direction::South => Direction::East,
-                            Direction::West => Direction::North,
-                            Direction::North => Direction::West,
+                        increment = if increment.is_east_west() {
+                            increment.turn_right()
+                        } else {
+                            increment.turn_left()
                         }
                     }
                     Device::NorthEastMirror => {
-                        increment = match increment {
-                            Direction::East => Direction::North,
-                            Direction::North => Direction::East,
-                            Direction::West => Direction::South,
-                            Direction::South => Direction::West,
+                        increment = if increment.is_east_west() {
+                            increment.turn_left()
+                        } else {
+                            increment.turn_right()
                         }
                     }
                 }
@@ -216,21 +180,18 @@ impl Contraption {
     pub fn max_energized(&self) -> u64 {
         let mut max = 0u64;
 
-        for j in 0..self.num_cols {
+        for j in 0..self.content.num_cols() {
             let count = self
-                .trace_beam_from(
-                    Position::new(0, j, (self.num_rows, self.num_cols)),
-                    Direction::South,
-                )
+                .trace_beam_from(self.content.position(0, j), Direction::South)
                 .num_energized();
             if count > max {
                 max = count;
             }
         }
-        for j in 0..self.num_cols {
+        for j in 0..self.content.num_cols() {
             let count = self
                 .trace_beam_from(
-                    Position::new(self.num_rows - 1, j, (self.num_rows, self.num_cols)),
+                    self.content.position(self.content.num_rows() - 1, j),
                     Direction::North,
                 )
                 .num_energized();
@@ -238,21 +199,18 @@ impl Contraption {
                 max = count;
             }
         }
-        for i in 0..self.num_rows {
+        for i in 0..self.content.num_rows() {
             let count = self
-                .trace_beam_from(
-                    Position::new(i, 0, (self.num_rows, self.num_cols)),
-                    Direction::East,
-                )
+                .trace_beam_from(self.content.position(i, 0), Direction::East)
                 .num_energized();
             if count > max {
                 max = count;
             }
         }
-        for i in 0..self.num_rows {
+        for i in 0..self.content.num_rows() {
             let count = self
                 .trace_beam_from(
-                    Position::new(i, self.num_cols - 1, (self.num_rows, self.num_cols)),
+                    self.content.position(i, self.content.num_cols() - 1),
                     Direction::West,
                 )
                 .num_energized();
@@ -267,23 +225,14 @@ impl Contraption {
 
 impl fmt::Display for Contraption {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.num_rows == 0 {
-            return write!(f, "");
-        }
-
-        let mut s = String::with_capacity(self.num_rows * (self.num_cols + 1) - 1);
-        for i in 0..self.num_rows {
-            for j in 0..self.num_cols {
-                s.push(match &self.content[i][j] {
-                    None => '.',
-                    Some(device) => device.into(),
-                });
-            }
-            if i < self.num_rows - 1 {
-                s.push('\n');
-            }
-        }
-        write!(f, "{}", s)
+        write!(
+            f,
+            "{}",
+            self.content.render(|cell| match cell {
+                Some(device) => device.into(),
+                None => '.',
+            })
+        )
     }
 }
 
@@ -308,6 +257,12 @@ mod tests {
         assert_eq!(format!("{}", c), INPUT);
     }
 
+    #[test]
+    fn test_parse_contraption_error() {
+        let e = Contraption::try_parse(".|..\n..x.").err().unwrap();
+        assert_eq!((e.line(), e.column()), (2, 3));
+    }
+
     #[test]
     fn test_trace_beam() {
         let c = Contraption::parse(&INPUT);
@@ -0,0 +1,286 @@
+use crate::error::{parse_token, ParseError};
+use crate::solution::Day;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Stage {
+    Parse,
+    Part1,
+    Part2,
+}
+
+impl Stage {
+    fn from_str(s: &str) -> Option<Stage> {
+        match s {
+            "parse" => Some(Stage::Parse),
+            "part1" => Some(Stage::Part1),
+            "part2" => Some(Stage::Part2),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stage::Parse => write!(f, "parse"),
+            Stage::Part1 => write!(f, "part1"),
+            Stage::Part2 => write!(f, "part2"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Stats {
+    min: Duration,
+    median: Duration,
+    max: Duration,
+}
+
+impl Stats {
+    pub fn new(min: Duration, median: Duration, max: Duration) -> Stats {
+        Stats { min, median, max }
+    }
+
+    /// Computes the statistics of a non-empty list of samples.
+    pub fn from_samples(samples: &[Duration]) -> Stats {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        Stats {
+            min: sorted[0],
+            median: sorted[sorted.len() / 2],
+            max: sorted[sorted.len() - 1],
+        }
+    }
+
+    pub fn min(&self) -> Duration {
+        self.min
+    }
+
+    pub fn median(&self) -> Duration {
+        self.median
+    }
+
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+}
+
+/// Timings of a benchmark, by day and stage.
+///
+/// The text format has one line per day and stage,
+/// `<day> <stage> <min> <median> <max>`, durations being in nanoseconds.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    stats: BTreeMap<(u32, Stage), Stats>,
+}
+
+/// A stage whose median time went over the baseline's.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Regression {
+    pub day: u32,
+    pub stage: Stage,
+    pub baseline: Duration,
+    pub median: Duration,
+}
+
+impl Report {
+    pub fn new() -> Report {
+        Report::default()
+    }
+
+    pub fn try_parse(input: &str) -> Result<Report, ParseError> {
+        let mut report = Report::new();
+
+        for line in input.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let day: u32 = parse_token(input, parts.next().unwrap(), "day")?;
+            let stage = parts
+                .next()
+                .ok_or_else(|| ParseError::at(input, &line[line.len()..], "stage"))?;
+            let stage = Stage::from_str(stage)
+                .ok_or_else(|| ParseError::at(input, stage, "'parse', 'part1' or 'part2'"))?;
+            let mut durations = [Duration::ZERO; 3];
+            for duration in durations.iter_mut() {
+                let nanos = parts.next().ok_or_else(|| {
+                    ParseError::at(input, &line[line.len()..], "duration in nanoseconds")
+                })?;
+                *duration =
+                    Duration::from_nanos(parse_token(input, nanos, "duration in nanoseconds")?);
+            }
+            if let Some(extra) = parts.next() {
+                return Err(ParseError::at(input, extra, "end of line"));
+            }
+
+            report.insert(
+                day,
+                stage,
+                Stats::new(durations[0], durations[1], durations[2]),
+            );
+        }
+
+        Ok(report)
+    }
+
+    pub fn insert(&mut self, day: u32, stage: Stage, stats: Stats) {
+        self.stats.insert((day, stage), stats);
+    }
+
+    pub fn get(&self, day: u32, stage: Stage) -> Option<&Stats> {
+        self.stats.get(&(day, stage))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, Stage, &Stats)> {
+        self.stats
+            .iter()
+            .map(|((day, stage), stats)| (*day, *stage, stats))
+    }
+
+    /// Adds the timings of `other`, replacing those of the same day and stage.
+    pub fn merge(&mut self, other: Report) {
+        self.stats.extend(other.stats);
+    }
+
+    /// Lists the stages whose median is more than `tolerance` (a fraction,
+    /// 0.1 for 10%) slower than in `baseline`.
+    pub fn regressions(&self, baseline: &Report, tolerance: f64) -> Vec<Regression> {
+        self.iter()
+            .filter_map(|(day, stage, stats)| {
+                let base = baseline.get(day, stage)?;
+                if stats.median.as_secs_f64() > base.median.as_secs_f64() * (1.0 + tolerance) {
+                    Some(Regression {
+                        day,
+                        stage,
+                        baseline: base.median,
+                        median: stats.median,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# day stage min median max (ns)")?;
+        for (day, stage, stats) in self.iter() {
+            writeln!(
+                f,
+                "{} {} {} {} {}",
+                day,
+                stage,
+                stats.min.as_nanos(),
+                stats.median.as_nanos(),
+                stats.max.as_nanos()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses `input` and solves `parts` `runs` times, timing each stage
+/// separately. Parts that are not solved are left out of the report.
+pub fn bench(day: &Day, input: &str, parts: &[u32], runs: usize) -> Result<Report, ParseError> {
+    let mut samples: BTreeMap<Stage, Vec<Duration>> = BTreeMap::new();
+
+    for _ in 0..runs.max(1) {
+        let start = Instant::now();
+        let solution = day.parse(input)?;
+        samples
+            .entry(Stage::Parse)
+            .or_default()
+            .push(start.elapsed());
+
+        for part in parts {
+            let start = Instant::now();
+            let (stage, answer) = match part {
+                1 => (Stage::Part1, solution.part1()),
+                _ => (Stage::Part2, solution.part2()),
+            };
+            if answer.is_some() {
+                samples.entry(stage).or_default().push(start.elapsed());
+            }
+        }
+    }
+
+    let mut report = Report::new();
+    for (stage, durations) in samples {
+        report.insert(day.number(), stage, Stats::from_samples(&durations));
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_stats() {
+        let s = Stats::from_samples(&[ms(4), ms(1), ms(9), ms(3), ms(5)]);
+        assert_eq!(s, Stats::new(ms(1), ms(4), ms(9)));
+    }
+
+    #[test]
+    fn test_report_round_trip() {
+        let mut r = Report::new();
+        r.insert(6, Stage::Part2, Stats::new(ms(1), ms(2), ms(3)));
+        r.insert(6, Stage::Parse, Stats::new(ms(4), ms(5), ms(6)));
+        let text = r.to_string();
+        assert!(
+            text.ends_with("6 parse 4000000 5000000 6000000\n6 part2 1000000 2000000 3000000\n")
+        );
+        assert_eq!(Report::try_parse(&text).unwrap(), r);
+
+        let e = Report::try_parse("6 part3 1 2 3").unwrap_err();
+        assert_eq!((e.line(), e.column()), (1, 3));
+    }
+
+    #[test]
+    fn test_regressions() {
+        let mut baseline = Report::new();
+        baseline.insert(1, Stage::Part1, Stats::new(ms(1), ms(10), ms(20)));
+        baseline.insert(1, Stage::Part2, Stats::new(ms(1), ms(10), ms(20)));
+        let mut r = Report::new();
+        r.insert(1, Stage::Part1, Stats::new(ms(1), ms(12), ms(20)));
+        r.insert(1, Stage::Part2, Stats::new(ms(1), ms(10), ms(20)));
+        r.insert(2, Stage::Part1, Stats::new(ms(1), ms(50), ms(90)));
+
+        assert_eq!(
+            r.regressions(&baseline, 0.1),
+            vec![Regression {
+                day: 1,
+                stage: Stage::Part1,
+                baseline: ms(10),
+                median: ms(12),
+            }]
+        );
+        assert!(r.regressions(&baseline, 0.5).is_empty());
+    }
+
+    #[test]
+    fn test_bench() {
+        let day = solution::day(6).unwrap();
+        let input = "Time:      7  15   30\nDistance:  9  40  200";
+        let r = bench(day, input, &[1, 2], 3).unwrap();
+        assert_eq!(r.iter().count(), 3);
+        assert!(r.get(6, Stage::Part1).is_some());
+
+        let r = bench(day, input, &[2], 3).unwrap();
+        assert!(r.get(6, Stage::Part1).is_none());
+        assert!(r.get(6, Stage::Part2).is_some());
+    }
+}
@@ -1,105 +1,433 @@
+use crate::error::ReadError;
+use crate::stream::parse_lines;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::io::BufRead;
+use std::ops::Range;
+
+const ENGLISH: [&str; 10] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
+/// Sums the calibration values of a document, digits being spelled with
+/// numerals or English words.
 pub fn sum(msg: &str) -> u32 {
-    let mut total = 0;
-    for line in msg.lines() {
-        total += calibration_from_line(line);
-    }
-    total
-}
-
-fn calibration_from_line(line: &str) -> u32 {
-    let mut first_digit: u32 = 0;
-    let mut last_digit: u32 = 0;
-    let mut found_first_digit = false;
-    let mut s = line;
-    while let (Some(digit), remaining_line) = next_digit(s) {
-        last_digit = digit;
-        if !found_first_digit {
-            first_digit = digit;
-            found_first_digit = true;
-        }
-        s = remaining_line;
-    }
-    first_digit * 10 + last_digit
+    CalibrationDecoder::english().sum(msg)
+}
+
+/// Lazily reads the calibration values of a document, one per line.
+pub fn stream_values<R: BufRead>(reader: R) -> impl Iterator<Item = Result<u32, ReadError>> {
+    CalibrationDecoder::english().stream_values(reader)
 }
 
-fn next_digit(line: &str) -> (Option<u32>, &str) {
-    let mut chars = line.chars();
-    let mut remaining_line = line;
-    while let Some(c) = chars.next() {
-        if c.is_digit(10) {
-            return (Some(c.to_digit(10).unwrap()), &remaining_line[1..]);
+/// Decodes calibration values, made of the first and last digits of a line.
+///
+/// Digits are the tokens of a vocabulary, each standing for a value. They are
+/// all searched for in a single pass over the line, and may overlap, as in
+/// "eightwo". When several tokens start at the same place, the longest wins.
+#[derive(Clone, Debug)]
+pub struct CalibrationDecoder {
+    automaton: Automaton,
+}
+
+impl CalibrationDecoder {
+    /// Creates a decoder for the tokens of `vocabulary`. Empty tokens are
+    /// ignored, and the last value of a repeated token is kept.
+    pub fn new<'a, I: IntoIterator<Item = (&'a str, u32)>>(vocabulary: I) -> CalibrationDecoder {
+        let vocabulary: BTreeMap<&str, u32> = vocabulary
+            .into_iter()
+            .filter(|(token, _)| !token.is_empty())
+            .collect();
+        CalibrationDecoder {
+            automaton: Automaton::new(&vocabulary),
         }
-        if remaining_line.starts_with("zero") {
-            return (Some(0), &remaining_line[1..]);
+    }
+
+    /// The numerals, from "0" to "9".
+    pub fn digits() -> CalibrationDecoder {
+        let numerals: Vec<String> = (0..10).map(|d| d.to_string()).collect();
+        CalibrationDecoder::new(numerals.iter().map(|n| n.as_str()).zip(0..))
+    }
+
+    /// The numerals and the English words from "zero" to "nine".
+    pub fn english() -> CalibrationDecoder {
+        let numerals: Vec<String> = (0..10).map(|d| d.to_string()).collect();
+        CalibrationDecoder::new(
+            numerals
+                .iter()
+                .map(|n| n.as_str())
+                .zip(0..)
+                .chain(ENGLISH.into_iter().zip(0..)),
+        )
+    }
+
+    /// Returns the calibration value of `line`, or `None` if it has no digit.
+    pub fn calibration_value(&self, line: &str) -> Option<u32> {
+        let mut ends = Ends::default();
+        self.automaton
+            .for_each_match(line, |start, len, value, _| ends.update(start, len, value));
+        Some(ends.first?.2 * 10 + ends.last?.2)
+    }
+
+    /// Lists the tokens found in `line`, and the digits chosen among them.
+    pub fn diagnose(&self, number: usize, line: &str) -> LineReport {
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut ends = Ends::default();
+        self.automaton
+            .for_each_match(line, |start, len, value, kind| {
+                ends.update(start, len, tokens.len());
+                tokens.push(Token {
+                    span: start..start + len,
+                    value,
+                    kind,
+                });
+            });
+
+        LineReport {
+            number,
+            first: ends.first.map(|(_, _, i)| tokens[i].clone()),
+            last: ends.last.map(|(_, _, i)| tokens[i].clone()),
+            text: line.to_string(),
+            tokens,
         }
-        if remaining_line.starts_with("one") {
-            return (Some(1), &remaining_line[1..]);
+    }
+
+    /// Diagnoses every line of `document`.
+    pub fn diagnose_document(&self, document: &str) -> Vec<LineReport> {
+        document
+            .lines()
+            .enumerate()
+            .map(|(i, line)| self.diagnose(i + 1, line))
+            .collect()
+    }
+
+    /// Sums the calibration values of the lines of `document`, lines without
+    /// digits counting for 0.
+    pub fn sum(&self, document: &str) -> u32 {
+        document
+            .lines()
+            .map(|line| self.calibration_value(line).unwrap_or(0))
+            .sum()
+    }
+
+    /// Lazily reads the calibration values of a document, one per line.
+    pub fn stream_values<R: BufRead>(
+        self,
+        reader: R,
+    ) -> impl Iterator<Item = Result<u32, ReadError>> {
+        parse_lines(reader, move |line| {
+            Ok(self.calibration_value(line).unwrap_or(0))
+        })
+    }
+}
+
+/// Whether a token is written with digits, like "7", or is a word, like "seven".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    Numeral,
+    Word,
+}
+
+/// A token found in a line, its span being in bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token {
+    pub span: Range<usize>,
+    pub value: u32,
+    pub kind: TokenKind,
+}
+
+/// What the decoder found in a line, with the line number starting at 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineReport {
+    pub number: usize,
+    pub text: String,
+    pub tokens: Vec<Token>,
+    pub first: Option<Token>,
+    pub last: Option<Token>,
+}
+
+impl LineReport {
+    pub fn has_digit(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+
+    pub fn value(&self) -> Option<u32> {
+        Some(self.first.as_ref()?.value * 10 + self.last.as_ref()?.value)
+    }
+}
+
+impl fmt::Display for LineReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let describe = |token: &Token| {
+            let kind = match token.kind {
+                TokenKind::Numeral => "numeral",
+                TokenKind::Word => "word",
+            };
+            format!(
+                "'{}' {}..{} {} {}",
+                &self.text[token.span.clone()],
+                token.span.start,
+                token.span.end,
+                kind,
+                token.value
+            )
+        };
+
+        match (&self.first, &self.last) {
+            (Some(first), Some(last)) => write!(
+                f,
+                "line {}: {} (first {}, last {})",
+                self.number,
+                first.value * 10 + last.value,
+                describe(first),
+                describe(last)
+            )?,
+            _ => write!(f, "line {}: no digit", self.number)?,
         }
-        if remaining_line.starts_with("two") {
-            return (Some(2), &remaining_line[1..]);
+        for token in &self.tokens {
+            write!(f, "\n    {}", describe(token))?;
         }
-        if remaining_line.starts_with("three") {
-            return (Some(3), &remaining_line[1..]);
+        Ok(())
+    }
+}
+
+/// The first and last of the tokens seen so far, by start. Of the tokens
+/// starting at the same place, the longest wins.
+struct Ends<T> {
+    first: Option<(usize, usize, T)>,
+    last: Option<(usize, usize, T)>,
+}
+
+impl<T> Default for Ends<T> {
+    fn default() -> Ends<T> {
+        Ends {
+            first: None,
+            last: None,
         }
-        if remaining_line.starts_with("four") {
-            return (Some(4), &remaining_line[1..]);
+    }
+}
+
+impl<T: Copy> Ends<T> {
+    fn update(&mut self, start: usize, len: usize, item: T) {
+        if self
+            .first
+            .is_none_or(|(s, l, _)| start < s || (start == s && len > l))
+        {
+            self.first = Some((start, len, item));
         }
-        if remaining_line.starts_with("five") {
-            return (Some(5), &remaining_line[1..]);
+        if self
+            .last
+            .is_none_or(|(s, l, _)| start > s || (start == s && len > l))
+        {
+            self.last = Some((start, len, item));
         }
-        if remaining_line.starts_with("six") {
-            return (Some(6), &remaining_line[1..]);
+    }
+}
+
+impl Default for CalibrationDecoder {
+    fn default() -> CalibrationDecoder {
+        CalibrationDecoder::english()
+    }
+}
+
+/// Aho-Corasick automaton over bytes, with its transitions fully resolved so
+/// that each byte of the text costs a single lookup.
+#[derive(Clone, Debug)]
+struct Automaton {
+    transitions: Vec<[u32; 256]>,
+    // length, value and kind of the tokens ending in each state
+    outputs: Vec<Vec<(usize, u32, TokenKind)>>,
+}
+
+impl Automaton {
+    fn new(vocabulary: &BTreeMap<&str, u32>) -> Automaton {
+        // trie of the tokens, 0 meaning no transition as the root is never
+        // the target of one
+        let mut transitions: Vec<[u32; 256]> = vec![[0; 256]];
+        let mut outputs: Vec<Vec<(usize, u32, TokenKind)>> = vec![Vec::new()];
+        for (token, value) in vocabulary {
+            let mut state = 0;
+            for byte in token.bytes() {
+                if transitions[state][byte as usize] == 0 {
+                    transitions.push([0; 256]);
+                    outputs.push(Vec::new());
+                    transitions[state][byte as usize] = (transitions.len() - 1) as u32;
+                }
+                state = transitions[state][byte as usize] as usize;
+            }
+            let kind = match token.bytes().all(|b| b.is_ascii_digit()) {
+                true => TokenKind::Numeral,
+                false => TokenKind::Word,
+            };
+            outputs[state].push((token.len(), *value, kind));
         }
-        if remaining_line.starts_with("seven") {
-            return (Some(7), &remaining_line[1..]);
+
+        // breadth first, so that the failure state of a state, being
+        // shallower, is complete when the state is reached
+        let mut failures = vec![0usize; transitions.len()];
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        for child in transitions[0] {
+            if child != 0 {
+                queue.push_back(child as usize);
+            }
         }
-        if remaining_line.starts_with("eight") {
-            return (Some(8), &remaining_line[1..]);
+        while let Some(state) = queue.pop_front() {
+            let failure = failures[state];
+            let inherited = outputs[failure].clone();
+            outputs[state].extend(inherited);
+            let fallback = transitions[failure];
+            for (next, fallback) in transitions[state].iter_mut().zip(fallback) {
+                if *next != 0 {
+                    failures[*next as usize] = fallback as usize;
+                    queue.push_back(*next as usize);
+                } else {
+                    *next = fallback;
+                }
+            }
         }
-        if remaining_line.starts_with("nine") {
-            return (Some(9), &remaining_line[1..]);
+
+        Automaton {
+            transitions,
+            outputs,
         }
+    }
 
-        remaining_line = &remaining_line[c.len_utf8()..];
+    /// Calls `f` with the start, length, value and kind of every token in
+    /// `text`, by increasing end.
+    fn for_each_match<F: FnMut(usize, usize, u32, TokenKind)>(&self, text: &str, mut f: F) {
+        let mut state = 0;
+        for (i, byte) in text.bytes().enumerate() {
+            state = self.transitions[state][byte as usize] as usize;
+            for (len, value, kind) in &self.outputs[state] {
+                f(i + 1 - len, *len, *value, *kind);
+            }
+        }
     }
-    (None, "")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_overlapping_words() {
+        let decoder = CalibrationDecoder::english();
+        assert_eq!(decoder.calibration_value("eightwo"), Some(82));
+        assert_eq!(decoder.calibration_value("oneight"), Some(18));
+        assert_eq!(decoder.calibration_value("twone"), Some(21));
+        assert_eq!(decoder.calibration_value("abc"), None);
+    }
+
     #[test]
     fn test_next_digit() {
-        let (mut next, remaining) = next_digit("eightwothree");
-        assert_eq!(next.unwrap(), 8);
-        assert_eq!(remaining, "ightwothree");
-        (next, _) = next_digit(remaining);
-        assert_eq!(next.unwrap(), 2);
+        // a digit can start inside the word of the previous one
+        let tokens = CalibrationDecoder::english()
+            .diagnose(1, "eightwothree")
+            .tokens;
+        assert_eq!((tokens[0].value, tokens[0].span.clone()), (8, 0..5));
+        assert_eq!((tokens[1].value, tokens[1].span.clone()), (2, 4..7));
+        assert_eq!((tokens[2].value, tokens[2].span.clone()), (3, 7..12));
     }
 
     #[test]
     fn test_calibration_from_line() {
-        assert_eq!(calibration_from_line("1abc2"), 12);
-        assert_eq!(calibration_from_line("pqr3stu8vwx"), 38);
-        assert_eq!(calibration_from_line("a1b2c3d4e5f"), 15);
-        assert_eq!(calibration_from_line("treb7uchet"), 77);
+        let decoder = CalibrationDecoder::english();
+        assert_eq!(decoder.calibration_value("1abc2"), Some(12));
+        assert_eq!(decoder.calibration_value("pqr3stu8vwx"), Some(38));
+        assert_eq!(decoder.calibration_value("a1b2c3d4e5f"), Some(15));
+        assert_eq!(decoder.calibration_value("treb7uchet"), Some(77));
 
-        assert_eq!(calibration_from_line("two1nine"), 29);
-        assert_eq!(calibration_from_line("eightwothree"), 83);
-        assert_eq!(calibration_from_line("abcone2threexyz"), 13);
-        assert_eq!(calibration_from_line("xtwone3four"), 24);
-        assert_eq!(calibration_from_line("4nineeightseven2"), 42);
-        assert_eq!(calibration_from_line("zoneight234"), 14);
-        assert_eq!(calibration_from_line("7pqrstsixteen"), 76);
+        assert_eq!(decoder.calibration_value("two1nine"), Some(29));
+        assert_eq!(decoder.calibration_value("eightwothree"), Some(83));
+        assert_eq!(decoder.calibration_value("abcone2threexyz"), Some(13));
+        assert_eq!(decoder.calibration_value("xtwone3four"), Some(24));
+        assert_eq!(decoder.calibration_value("4nineeightseven2"), Some(42));
+        assert_eq!(decoder.calibration_value("zoneight234"), Some(14));
+        assert_eq!(decoder.calibration_value("7pqrstsixteen"), Some(76));
     }
 
     #[test]
-    fn test_sum() {
+    fn test_digits_only() {
+        let decoder = CalibrationDecoder::digits();
+        assert_eq!(decoder.calibration_value("two1nine"), Some(11));
+        assert_eq!(decoder.calibration_value("eightwothree"), None);
         assert_eq!(
-            sum("1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet"),
+            decoder.sum("1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet"),
             142
         );
+    }
+
+    #[test]
+    fn test_custom_vocabulary() {
+        let decoder = CalibrationDecoder::new([("un", 1), ("deux", 2), ("trois", 3), ("", 9)]);
+        assert_eq!(decoder.calibration_value("xtroisundeuxy"), Some(32));
+        assert_eq!(decoder.calibration_value("one2"), None);
+
+        // the longest token wins when several start at the same place
+        let decoder = CalibrationDecoder::new([("do", 2), ("dozen", 1), ("zen", 7)]);
+        assert_eq!(decoder.calibration_value("dozen"), Some(17));
+        assert_eq!(decoder.calibration_value("xdoz"), Some(22));
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let decoder = CalibrationDecoder::english();
+        let report = decoder.diagnose(2, "xeightwo3");
+        assert!(report.has_digit());
+        assert_eq!(report.value(), Some(83));
+        assert_eq!(
+            report.tokens,
+            vec![
+                Token {
+                    span: 1..6,
+                    value: 8,
+                    kind: TokenKind::Word
+                },
+                Token {
+                    span: 5..8,
+                    value: 2,
+                    kind: TokenKind::Word
+                },
+                Token {
+                    span: 8..9,
+                    value: 3,
+                    kind: TokenKind::Numeral
+                },
+            ]
+        );
+        assert_eq!(report.first, Some(report.tokens[0].clone()));
+        assert_eq!(report.last, Some(report.tokens[2].clone()));
+        assert_eq!(
+            report.to_string(),
+            "line 2: 83 (first 'eight' 1..6 word 8, last '3' 8..9 numeral 3)
+    'eight' 1..6 word 8
+    'two' 5..8 word 2
+    '3' 8..9 numeral 3"
+        );
+    }
+
+    #[test]
+    fn test_diagnose_document() {
+        let reports = CalibrationDecoder::digits().diagnose_document("two1nine\nabc\n");
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].value(), Some(11));
+        assert!(!reports[1].has_digit());
+        assert_eq!(reports[1].value(), None);
+        assert_eq!(reports[1].to_string(), "line 2: no digit");
+    }
+
+    #[test]
+    fn test_sum() {
+        assert_eq!(sum("1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet"), 142);
         assert_eq!(sum("two1nine\neightwothree\nabcone2threexyz\nxtwone3four\n4nineeightseven2\nzoneight234\n7pqrstsixteen"), 281);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_stream_values() {
+        let values: Result<Vec<u32>, ReadError> =
+            stream_values("two1nine\r\neightwothree\n".as_bytes()).collect();
+        assert_eq!(values.unwrap(), vec![29, 83]);
+        let sum: Result<u32, ReadError> = stream_values("1abc2\ntreb7uchet".as_bytes()).sum();
+        assert_eq!(sum.unwrap(), 89);
+    }
+}
@@ -1,3 +1,6 @@
+use crate::error::{parse_token, Found, ParseError, ReadError};
+use crate::stream::parse_lines;
+use std::io::BufRead;
 use std::{cmp::Ordering, collections::BTreeMap};
 
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
@@ -18,7 +21,7 @@ pub enum Card {
 }
 
 impl TryFrom<char> for Card {
-    type Error = &'static str;
+    type Error = ParseError;
 
     fn try_from(c: char) -> Result<Self, Self::Error> {
         match c {
@@ -35,7 +38,12 @@ impl TryFrom<char> for Card {
             '3' => Ok(Card::Three),
             '2' => Ok(Card::Two),
             'J' => Ok(Card::J),
-            _ => Err("invalid card character"),
+            _ => Err(ParseError::new(
+                1,
+                1,
+                "card (A, K, Q, J, T or 2 to 9)",
+                Found::Token(c.to_string()),
+            )),
         }
     }
 }
@@ -51,24 +59,33 @@ pub enum HandType {
     HighCard = 0,
 }
 
-#[derive(Eq, Debug)]
+#[derive(Clone, Eq, Debug)]
 pub struct Hand {
     cards: [Card; 5],
 }
 
 impl Hand {
     pub fn parse(hand: &str) -> Hand {
+        Hand::try_parse(hand).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_parse(hand: &str) -> Result<Hand, ParseError> {
         let mut h = Hand {
             cards: [Card::Two; 5],
         };
 
-        let mut cursor: usize = 0;
-        for c in hand.chars() {
-            h.cards[cursor] = Card::try_from(c).unwrap();
-            cursor += 1;
+        let mut chars = hand.char_indices();
+        for card in h.cards.iter_mut() {
+            let (i, c) = chars
+                .next()
+                .ok_or_else(|| ParseError::at_end(hand, "card"))?;
+            *card = Card::try_from(c).map_err(|e| e.within(hand, &hand[i..i + c.len_utf8()]))?;
+        }
+        if let Some((i, _)) = chars.next() {
+            return Err(ParseError::at(hand, &hand[i..], "end of hand"));
         }
 
-        h
+        Ok(h)
     }
 
     pub fn hand_type(&self) -> HandType {
@@ -81,7 +98,7 @@ impl Hand {
                 counter.entry(card).and_modify(|c| *c += 1).or_insert(1u8);
             }
         }
-        if count_j==5{
+        if count_j == 5 {
             return HandType::FiveOfKind;
         }
 
@@ -166,11 +183,31 @@ pub fn total_winnings2(bids: &mut Vec<(Hand, u64)>) -> u64 {
 }
 
 pub fn read_bids2(input: &str) -> Vec<(Hand, u64)> {
-    let mut bids: Vec<(Hand, u64)> = Vec::new();
-    for line in input.lines() {
-        bids.push((Hand::parse(&line[0..5]), line[5..].trim().parse().unwrap()));
-    }
-    bids
+    try_read_bids2(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_read_bids2(input: &str) -> Result<Vec<(Hand, u64)>, ParseError> {
+    input
+        .lines()
+        .map(|line| try_parse_bid(line).map_err(|e| e.within(input, line)))
+        .collect()
+}
+
+/// Lazily reads bids, one per line.
+pub fn stream_bids2<R: BufRead>(reader: R) -> impl Iterator<Item = Result<(Hand, u64), ReadError>> {
+    parse_lines(reader, try_parse_bid)
+}
+
+fn try_parse_bid(line: &str) -> Result<(Hand, u64), ParseError> {
+    let mut parts = line.split_whitespace();
+    let str_hand = parts
+        .next()
+        .ok_or_else(|| ParseError::at(line, line, "hand"))?;
+    let hand = Hand::try_parse(str_hand).map_err(|e| e.within(line, str_hand))?;
+    let str_bid = parts
+        .next()
+        .ok_or_else(|| ParseError::at_end(line, "bid"))?;
+    Ok((hand, parse_token(line, str_bid, "bid")?))
 }
 
 #[cfg(test)]
@@ -220,6 +257,36 @@ mod tests {
         assert!(Hand::parse("KTJJT") == Hand::parse("KTJJT"));
     }
 
+    #[test]
+    fn test_read_bids_errors() {
+        let e = try_read_bids2("32T3K 765\nT55X5 684").unwrap_err();
+        assert_eq!((e.line(), e.column()), (2, 4));
+
+        let e = try_read_bids2("32T3K 765\nT55J5").unwrap_err();
+        assert_eq!((e.line(), e.column()), (2, 6));
+        assert_eq!(e.found(), &Found::EndOfInput);
+
+        let e = try_read_bids2("32T3 765").unwrap_err();
+        assert_eq!((e.line(), e.column()), (1, 5));
+    }
+
+    #[test]
+    fn test_stream_bids() {
+        let mut bids: Vec<(Hand, u64)> = stream_bids2("32T3K 765\r\nT55J5 684\n".as_bytes())
+            .map(|bid| bid.unwrap())
+            .collect();
+        assert_eq!(bids[1], (Hand::parse("T55J5"), 684));
+        assert_eq!(total_winnings2(&mut bids), 765 + 2 * 684);
+
+        match stream_bids2("32T3K 765\nT55J5\n".as_bytes()).nth(1) {
+            Some(Err(ReadError::Parse(e))) => {
+                assert_eq!((e.line(), e.column()), (2, 6));
+                assert_eq!(e.found(), &Found::EndOfLine);
+            }
+            _ => panic!("expected a parse error"),
+        }
+    }
+
     #[test]
     fn test_total_winnings() {
         let mut bids: Vec<(Hand, u64)> = Vec::from([
@@ -1,82 +1,307 @@
-const MAX_RED: u32 = 12;
-const MAX_GREEN: u32 = 13;
-const MAX_BLUE: u32 = 14;
+use crate::error::{parse_token, ParseError, ReadError};
+use crate::stream::parse_lines;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::BufRead;
 
 pub fn sum_game_ids(msg: &str) -> u32 {
-    let mut total = 0;
-    for line in msg.lines() {
-        let g = Game::parse(line);
-        if g.is_possible() {
-            total += g.id();
+    sum_game_ids_with(msg, &Bag::standard())
+}
+
+pub fn sum_min_cubes_powers(msg: &str) -> u32 {
+    sum_min_cubes_powers_with(msg, &Bag::standard())
+}
+
+/// Sums the ids of the games possible with `bag`, whose colours are the only
+/// ones allowed in the games.
+pub fn sum_game_ids_with(msg: &str, bag: &Bag) -> u32 {
+    let games = try_read_games_with(msg, bag).unwrap_or_else(|e| panic!("{}", e));
+    sum_possible_game_ids(&games, bag)
+}
+
+/// Sums the powers of the minimal sets of cubes of the colours of `bag`,
+/// which are the only ones allowed in the games.
+pub fn sum_min_cubes_powers_with(msg: &str, bag: &Bag) -> u32 {
+    let games = try_read_games_with(msg, bag).unwrap_or_else(|e| panic!("{}", e));
+    sum_min_cubes_powers_of(&games, bag)
+}
+
+pub fn try_read_games(msg: &str) -> Result<Vec<Game>, ParseError> {
+    try_read_games_with(msg, &Bag::standard())
+}
+
+pub fn try_read_games_with(msg: &str, bag: &Bag) -> Result<Vec<Game>, ParseError> {
+    msg.lines()
+        .map(|line| Game::try_parse_with(line, bag).map_err(|e| e.within(msg, line)))
+        .collect()
+}
+
+/// Lazily reads games, one per line, with the colours of the standard bag.
+pub fn stream_games<R: BufRead>(reader: R) -> impl Iterator<Item = Result<Game, ReadError>> {
+    parse_lines(reader, Game::try_parse)
+}
+
+/// Same as `sum_game_ids`, reading the games one at a time.
+pub fn sum_game_ids_from<R: BufRead>(reader: R) -> Result<u32, ReadError> {
+    let bag = Bag::standard();
+    stream_games(reader)
+        .map(|g| g.map(|g| if g.is_possible(&bag) { g.id() } else { 0 }))
+        .sum()
+}
+
+/// Same as `sum_min_cubes_powers`, reading the games one at a time.
+pub fn sum_min_cubes_powers_from<R: BufRead>(reader: R) -> Result<u32, ReadError> {
+    let bag = Bag::standard();
+    stream_games(reader).map(|g| g.map(|g| g.power(&bag))).sum()
+}
+
+pub fn sum_possible_game_ids(games: &[Game], bag: &Bag) -> u32 {
+    games
+        .iter()
+        .filter(|g| g.is_possible(bag))
+        .map(|g| g.id())
+        .sum()
+}
+
+pub fn sum_min_cubes_powers_of(games: &[Game], bag: &Bag) -> u32 {
+    games.iter().map(|g| g.power(bag)).sum()
+}
+
+/// Returns the smallest bag with which every game is possible, its colours
+/// being those drawn in the games, in alphabetical order.
+///
+/// The bags consistent with all the games are exactly those containing it.
+pub fn min_bag(games: &[Game]) -> Bag {
+    let mut cubes: BTreeMap<&str, u32> = BTreeMap::new();
+    for draw in games.iter().flat_map(|g| g.draws()) {
+        for (colour, count) in draw.colours() {
+            let min = cubes.entry(colour).or_insert(0);
+            *min = (*min).max(count);
         }
     }
-    total
+    Bag::new(cubes)
 }
 
-pub fn sum_min_cubes_powers(msg: &str) -> u32 {
-    let mut total = 0;
-    for line in msg.lines() {
-        let g = Game::parse(line);
-        let cubes = g.min_cubes();
-        total += cubes.0 * cubes.1 * cubes.2;
+/// Lists the bags with `total` cubes with which every game is possible, with
+/// the colours of `min_bag`.
+pub fn consistent_bags(games: &[Game], total: u32) -> Vec<Bag> {
+    let min = min_bag(games);
+    let required: u32 = min.cubes.iter().map(|(_, count)| count).sum();
+    if total < required || (min.cubes.is_empty() && total > 0) {
+        return Vec::new();
+    }
+
+    // spread the spare cubes over the colours in every possible way
+    let mut bags: Vec<Bag> = Vec::new();
+    let mut spread = vec![0u32; min.cubes.len()];
+    spread_spare_cubes(&min, &mut spread, 0, total - required, &mut bags);
+    bags
+}
+
+fn spread_spare_cubes(min: &Bag, spread: &mut [u32], i: usize, spare: u32, bags: &mut Vec<Bag>) {
+    if i + 1 >= spread.len() {
+        if let Some(last) = spread.last_mut() {
+            *last = spare;
+        }
+        bags.push(Bag::new(
+            min.cubes
+                .iter()
+                .zip(spread.iter())
+                .map(|((colour, count), extra)| (colour.as_str(), count + extra)),
+        ));
+        return;
+    }
+    for extra in 0..=spare {
+        spread[i] = extra;
+        spread_spare_cubes(min, spread, i + 1, spare - extra, bags);
     }
-    total
 }
 
-struct Draw {
-    r: u32,
-    g: u32,
-    b: u32,
+/// Lists the draws of all the games that could not be taken out of `bag`.
+pub fn conflicts(games: &[Game], bag: &Bag) -> Vec<Conflict> {
+    games.iter().flat_map(|g| g.conflicts(bag)).collect()
 }
 
-impl Draw {
-    pub fn parse(s: &str) -> Draw {
-        let mut r: u32 = 0;
-        let mut g: u32 = 0;
-        let mut b: u32 = 0;
-
-        s.split(",").for_each(|str_cubes| {
-            let s = str_cubes.trim();
-            let mut iter = s.split_whitespace();
-            let num: u32 = iter.next().unwrap().parse().unwrap();
-            let color: &str = iter.next().unwrap();
-            if color == "red" {
-                r += num;
-            } else if color == "green" {
-                g += num;
-            } else {
-                b += num;
+/// A colour of a draw with more cubes than the bag, a colour missing from the
+/// bag having a limit of 0. Draws are numbered from 1 in their game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    pub game: u32,
+    pub draw: usize,
+    pub colour: String,
+    pub count: u32,
+    pub limit: u32,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "game {}, draw {}: {} {} cubes, the bag has {}",
+            self.game, self.draw, self.count, self.colour, self.limit
+        )
+    }
+}
+
+/// Colours of cubes, with the number of cubes of each colour in the bag.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bag {
+    cubes: Vec<(String, u32)>,
+}
+
+impl Bag {
+    /// Creates a bag with the given number of cubes of each colour. The last
+    /// number is kept for a repeated colour.
+    pub fn new<'a, I: IntoIterator<Item = (&'a str, u32)>>(cubes: I) -> Bag {
+        let mut bag = Bag { cubes: Vec::new() };
+        for (colour, limit) in cubes {
+            match bag.cubes.iter_mut().find(|(c, _)| c == colour) {
+                Some(cube) => cube.1 = limit,
+                None => bag.cubes.push((colour.to_string(), limit)),
             }
-        });
+        }
+        bag
+    }
 
-        Draw { r, g, b }
+    /// The bag of the puzzle: 12 red cubes, 13 green cubes and 14 blue cubes.
+    pub fn standard() -> Bag {
+        Bag::new([("red", 12), ("green", 13), ("blue", 14)])
     }
 
-    pub fn is_possible(&self) -> bool {
-        if self.r > MAX_RED || self.g > MAX_GREEN || self.b > MAX_BLUE {
-            return false;
+    pub fn colours(&self) -> impl Iterator<Item = &str> {
+        self.cubes.iter().map(|(colour, _)| colour.as_str())
+    }
+
+    /// Returns the number of cubes of `colour`, or `None` if the bag has no
+    /// such colour.
+    pub fn limit(&self, colour: &str) -> Option<u32> {
+        self.cubes
+            .iter()
+            .find(|(c, _)| c == colour)
+            .map(|(_, limit)| *limit)
+    }
+
+    /// Tells whether every colour of `other` has at most as many cubes as in
+    /// this bag.
+    pub fn contains(&self, other: &Bag) -> bool {
+        other
+            .cubes
+            .iter()
+            .all(|(colour, count)| self.limit(colour).unwrap_or(0) >= *count)
+    }
+
+    /// Tells whether `draw` could be taken out of the bag.
+    pub fn allows(&self, draw: &Draw) -> bool {
+        draw.counts
+            .iter()
+            .all(|(colour, count)| self.limit(colour).is_some_and(|limit| *count <= limit))
+    }
+
+    // "red, green or blue"
+    fn describe_colours(&self) -> String {
+        let colours: Vec<&str> = self.colours().collect();
+        match colours.split_last() {
+            None => "no colour".to_string(),
+            Some((last, [])) => last.to_string(),
+            Some((last, others)) => format!("{} or {}", others.join(", "), last),
         }
-        true
     }
 }
 
-struct Game {
+impl fmt::Display for Bag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cubes: Vec<String> = self
+            .cubes
+            .iter()
+            .map(|(colour, limit)| format!("{} {}", limit, colour))
+            .collect();
+        write!(f, "{}", cubes.join(", "))
+    }
+}
+
+pub struct Draw {
+    counts: BTreeMap<String, u32>,
+}
+
+impl Draw {
+    pub fn new<'a, I: IntoIterator<Item = (&'a str, u32)>>(counts: I) -> Draw {
+        let mut draw = Draw {
+            counts: BTreeMap::new(),
+        };
+        for (colour, count) in counts {
+            *draw.counts.entry(colour.to_string()).or_insert(0) += count;
+        }
+        draw
+    }
+
+    /// Parses a draw whose colours are those of `bag`.
+    pub fn try_parse(s: &str, bag: &Bag) -> Result<Draw, ParseError> {
+        let mut draw = Draw::new([]);
+
+        for str_cubes in s.split(',') {
+            let mut iter = str_cubes.split_whitespace();
+            let str_num = iter.next().ok_or_else(|| {
+                ParseError::at(s, &str_cubes[str_cubes.len()..], "number of cubes")
+            })?;
+            let num: u32 = parse_token(s, str_num, "number of cubes")?;
+            let colour = iter
+                .next()
+                .ok_or_else(|| ParseError::at(s, &str_cubes[str_cubes.len()..], "colour"))?;
+            if bag.limit(colour).is_none() {
+                return Err(ParseError::at(s, colour, &bag.describe_colours()));
+            }
+            *draw.counts.entry(colour.to_string()).or_insert(0) += num;
+        }
+
+        Ok(draw)
+    }
+
+    /// Iterates over the colours of the draw, in alphabetical order, with
+    /// their numbers of cubes.
+    pub fn colours(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.counts
+            .iter()
+            .map(|(colour, count)| (colour.as_str(), *count))
+    }
+
+    /// Returns the number of cubes of `colour` in the draw.
+    pub fn count(&self, colour: &str) -> u32 {
+        self.counts.get(colour).copied().unwrap_or(0)
+    }
+
+    pub fn is_possible(&self, bag: &Bag) -> bool {
+        bag.allows(self)
+    }
+}
+
+pub struct Game {
     id: u32,
     draws: Vec<Draw>,
 }
 
 impl Game {
     pub fn parse(msg: &str) -> Game {
-        let s = msg.strip_prefix("Game ").unwrap();
-        let column_index = s.find(":").unwrap();
-        let id: u32 = (&s[0..column_index]).parse().unwrap();
+        Game::try_parse(msg).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_parse(msg: &str) -> Result<Game, ParseError> {
+        Game::try_parse_with(msg, &Bag::standard())
+    }
+
+    /// Parses a game whose colours are those of `bag`.
+    pub fn try_parse_with(msg: &str, bag: &Bag) -> Result<Game, ParseError> {
+        let s = msg.strip_prefix("Game ").ok_or_else(|| {
+            ParseError::at(msg, &msg[..msg.find(' ').unwrap_or(msg.len())], "'Game '")
+        })?;
+        let column_index = s.find(':').ok_or_else(|| ParseError::at_end(msg, "':'"))?;
+        let id: u32 = parse_token(msg, &s[0..column_index], "game id")?;
         let mut game = Game::new(id);
 
-        let _ = &s[column_index + 1..]
-            .split(";")
-            .for_each(|str_draw| game.add(Draw::parse(str_draw)));
+        for str_draw in s[column_index + 1..].split(';') {
+            game.add(Draw::try_parse(str_draw, bag).map_err(|e| e.within(msg, str_draw))?);
+        }
 
-        game
+        Ok(game)
     }
 
     pub fn new(id: u32) -> Game {
@@ -94,95 +319,260 @@ impl Game {
         self.draws.push(d);
     }
 
-    pub fn is_possible(&self) -> bool {
-        for draw in self.draws.as_slice() {
-            if !draw.is_possible() {
-                return false;
-            }
-        }
-        true
+    pub fn draws(&self) -> &[Draw] {
+        &self.draws
+    }
+
+    pub fn is_possible(&self, bag: &Bag) -> bool {
+        self.draws.iter().all(|draw| draw.is_possible(bag))
     }
 
-    pub fn min_cubes(&self) -> (u32, u32, u32) {
-        let mut min_r: u32 = 0;
-        let mut min_g: u32 = 0;
-        let mut min_b: u32 = 0;
+    /// Returns the minimal number of cubes of `colour` for the game to be
+    /// possible.
+    pub fn min_cubes(&self, colour: &str) -> u32 {
+        self.draws
+            .iter()
+            .map(|draw| draw.count(colour))
+            .max()
+            .unwrap_or(0)
+    }
 
-        for draw in self.draws.as_slice() {
-            if draw.r > min_r {
-                min_r = draw.r;
-            }
-            if draw.g > min_g {
-                min_g = draw.g;
-            }
-            if draw.b > min_b {
-                min_b = draw.b;
+    /// Returns the product of the minimal numbers of cubes of the colours of
+    /// `bag`.
+    pub fn power(&self, bag: &Bag) -> u32 {
+        bag.colours().map(|colour| self.min_cubes(colour)).product()
+    }
+
+    /// Returns the smallest bag with which the game is possible.
+    pub fn min_bag(&self) -> Bag {
+        min_bag(std::slice::from_ref(self))
+    }
+
+    /// Lists the draws that could not be taken out of `bag`.
+    pub fn conflicts(&self, bag: &Bag) -> Vec<Conflict> {
+        let mut conflicts: Vec<Conflict> = Vec::new();
+        for (i, draw) in self.draws.iter().enumerate() {
+            for (colour, count) in draw.colours() {
+                let limit = bag.limit(colour).unwrap_or(0);
+                if count > limit {
+                    conflicts.push(Conflict {
+                        game: self.id,
+                        draw: i + 1,
+                        colour: colour.to_string(),
+                        count,
+                        limit,
+                    });
+                }
             }
         }
-
-        (min_r, min_g, min_b)
+        conflicts
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::Found;
+
+    fn rgb(r: u32, g: u32, b: u32) -> Draw {
+        Draw::new([("red", r), ("green", g), ("blue", b)])
+    }
 
     #[test]
     fn test_is_draw_possible() {
-        assert!(Draw { r: 3, g: 4, b: 1 }.is_possible());
-        assert!(!Draw { r: 8, g: 6, b: 15 }.is_possible());
-        assert!(!Draw { r: 11, g: 14, b: 3 }.is_possible());
-        assert!(!Draw { r: 13, g: 11, b: 0 }.is_possible());
+        let bag = Bag::standard();
+        assert!(rgb(3, 4, 1).is_possible(&bag));
+        assert!(!rgb(8, 6, 15).is_possible(&bag));
+        assert!(!rgb(11, 14, 3).is_possible(&bag));
+        assert!(!rgb(13, 11, 0).is_possible(&bag));
     }
 
     #[test]
     fn test_is_game_possible() {
+        let bag = Bag::standard();
         let mut game = Game::new(1);
-        game.add(Draw { r: 3, g: 4, b: 1 });
-        game.add(Draw { r: 5, g: 8, b: 3 });
-        assert!(game.is_possible());
+        game.add(rgb(3, 4, 1));
+        game.add(rgb(5, 8, 3));
+        assert!(game.is_possible(&bag));
+
+        game.add(rgb(8, 14, 7));
+        assert!(!game.is_possible(&bag));
+    }
+
+    #[test]
+    fn test_bag() {
+        let bag = Bag::new([("red", 1), ("cyan", 2), ("red", 3)]);
+        assert_eq!(bag.colours().collect::<Vec<&str>>(), vec!["red", "cyan"]);
+        assert_eq!(bag.limit("red"), Some(3));
+        assert_eq!(bag.limit("blue"), None);
+        assert_eq!(bag.to_string(), "3 red, 2 cyan");
 
-        game.add(Draw { r: 8, g: 14, b: 7 });
-        assert!(!game.is_possible());
+        assert!(bag.allows(&Draw::new([("cyan", 2)])));
+        assert!(!bag.allows(&Draw::new([("cyan", 3)])));
+        assert!(!bag.allows(&Draw::new([("blue", 1)])));
     }
 
     #[test]
     fn test_parse_game() {
+        let bag = Bag::standard();
         let g1 = Game::parse("Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green");
         assert_eq!(g1.id(), 1);
-        assert!(g1.is_possible());
+        assert!(g1.is_possible(&bag));
 
         let g2 = Game::parse("Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue");
         assert_eq!(g2.id(), 2);
-        assert!(g2.is_possible());
+        assert!(g2.is_possible(&bag));
 
         let g3 =
             Game::parse("Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red");
         assert_eq!(g3.id(), 3);
-        assert!(!g3.is_possible());
+        assert!(!g3.is_possible(&bag));
 
         let g4 =
             Game::parse("Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red");
         assert_eq!(g4.id(), 4);
-        assert!(!g4.is_possible());
+        assert!(!g4.is_possible(&bag));
+        assert_eq!(
+            (
+                g4.min_cubes("red"),
+                g4.min_cubes("green"),
+                g4.min_cubes("blue")
+            ),
+            (14, 3, 15)
+        );
 
         let g5 = Game::parse("Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green");
         assert_eq!(g5.id(), 5);
-        assert!(g5.is_possible());
+        assert!(g5.is_possible(&bag));
     }
 
+    #[test]
+    fn test_parse_game_errors() {
+        let e = Game::try_parse("Game 1: 3 blue; 4 purple").err().unwrap();
+        assert_eq!((e.line(), e.column()), (1, 19));
+        assert_eq!(e.expected(), "red, green or blue");
+
+        let e = Game::try_parse("Game 1: 3 blue, 4").err().unwrap();
+        assert_eq!((e.line(), e.column()), (1, 18));
+        assert_eq!(e.found(), &Found::EndOfInput);
+
+        let e = Game::try_parse("Gam 1: 3 blue").err().unwrap();
+        assert_eq!((e.line(), e.column()), (1, 1));
+
+        let bag = Bag::new([("cyan", 5), ("magenta", 5)]);
+        let e = Game::try_parse_with("Game 1: 3 cyan; 4 red", &bag)
+            .err()
+            .unwrap();
+        assert_eq!((e.line(), e.column()), (1, 19));
+        assert_eq!(e.expected(), "cyan or magenta");
+    }
+
+    const INPUT: &str = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
+
     #[test]
     fn test_sum_game_ids() {
+        assert_eq!(sum_game_ids(INPUT), 8);
+        assert_eq!(sum_min_cubes_powers(INPUT), 2286);
+    }
+
+    #[test]
+    fn test_sums_with_bag() {
+        let bag = Bag::new([("red", 20), ("green", 13), ("blue", 14)]);
+        assert_eq!(sum_game_ids_with(INPUT, &bag), 11);
+
+        // only the colours of the bag count in the powers
+        let bag = Bag::new([("red", 20), ("blue", 14)]);
+        let input = "Game 1: 3 blue, 4 red; 1 red, 6 blue\nGame 2: 1 blue";
+        assert_eq!(sum_min_cubes_powers_with(input, &bag), 24);
+    }
+
+    #[test]
+    fn test_min_bag() {
+        let games = try_read_games(INPUT).unwrap();
+        let min = min_bag(&games);
+        assert_eq!(min, Bag::new([("blue", 15), ("green", 13), ("red", 20)]));
+        assert_eq!(games[0].min_bag().to_string(), "6 blue, 2 green, 4 red");
+
+        assert!(Bag::new([("red", 20), ("green", 13), ("blue", 16)]).contains(&min));
+        assert!(!Bag::standard().contains(&min));
+        assert!(games.iter().all(|g| g.is_possible(&min)));
+    }
+
+    #[test]
+    fn test_consistent_bags() {
+        let games = try_read_games(INPUT).unwrap();
+        assert!(consistent_bags(&games, 47).is_empty());
         assert_eq!(
-            sum_game_ids(
-                "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green\n\
-        Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue\n\
-        Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red\n\
-        Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red\n\
-        Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green"
-            ),
-            8
-        )
+            consistent_bags(&games, 48),
+            vec![Bag::new([("blue", 15), ("green", 13), ("red", 20)])]
+        );
+
+        let bags = consistent_bags(&games, 50);
+        assert_eq!(bags.len(), 6);
+        assert!(bags.contains(&Bag::new([("blue", 15), ("green", 15), ("red", 20)])));
+        assert!(bags
+            .iter()
+            .all(|bag| games.iter().all(|g| g.is_possible(bag))));
+
+        assert_eq!(consistent_bags(&[], 0), vec![Bag::new([])]);
+        assert!(consistent_bags(&[], 1).is_empty());
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let games = try_read_games(INPUT).unwrap();
+        let conflicts = conflicts(&games, &Bag::standard());
+        assert_eq!(
+            conflicts
+                .iter()
+                .map(|c| (c.game, c.draw))
+                .collect::<Vec<_>>(),
+            vec![(3, 1), (4, 3), (4, 3)]
+        );
+        assert_eq!(
+            conflicts[0].to_string(),
+            "game 3, draw 1: 20 red cubes, the bag has 12"
+        );
+
+        let bag = Bag::new([("red", 20), ("green", 20)]);
+        assert_eq!(
+            games[0].conflicts(&bag),
+            vec![
+                Conflict {
+                    game: 1,
+                    draw: 1,
+                    colour: "blue".to_string(),
+                    count: 3,
+                    limit: 0
+                },
+                Conflict {
+                    game: 1,
+                    draw: 2,
+                    colour: "blue".to_string(),
+                    count: 6,
+                    limit: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sums_from_reader() {
+        let input = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green\r\n\
+        Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red\n";
+        assert_eq!(sum_game_ids_from(input.as_bytes()).unwrap(), 1);
+        assert_eq!(
+            sum_min_cubes_powers_from(input.as_bytes()).unwrap(),
+            48 + 1560
+        );
+
+        match sum_game_ids_from("Game 1: 3 blue\nGame 2: 4 purple".as_bytes()) {
+            Err(ReadError::Parse(e)) => assert_eq!((e.line(), e.column()), (2, 11)),
+            _ => panic!("expected a parse error"),
+        }
     }
 }
@@ -1,47 +1,63 @@
+use crate::error::ParseError;
 use num::integer::lcm;
 use std::collections::HashMap;
 
-pub fn read_map(
-    input: &str,
-) -> (
+type Network = (
     Vec<Direction>,
     HashMap<String, (String, String)>,
     Vec<String>,
-) {
+);
+
+pub fn read_map(input: &str) -> Network {
+    try_read_map(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_read_map(input: &str) -> Result<Network, ParseError> {
     let mut instructions: Vec<Direction> = Vec::new();
     let mut map: HashMap<String, (String, String)> = HashMap::new();
     let mut a_nodes: Vec<String> = Vec::new();
 
     let mut lines = input.lines();
-    let str_instructions = lines.next().unwrap();
-    instructions.extend(
-        str_instructions
-            .chars()
-            .filter(|c| c.is_alphabetic())
-            .map(|c| match c {
-                'L' => Direction::L,
-                _ => Direction::R,
-            }),
-    );
+    let str_instructions = lines
+        .next()
+        .ok_or_else(|| ParseError::at_end(input, "instructions"))?;
+    for (i, c) in str_instructions.trim().char_indices() {
+        instructions.push(match c {
+            'L' => Direction::L,
+            'R' => Direction::R,
+            _ => {
+                let token = &str_instructions.trim()[i..i + c.len_utf8()];
+                return Err(ParseError::at(input, token, "'L' or 'R'"));
+            }
+        });
+    }
 
     lines.next();
     for line in lines {
-        let equ_index = line.find(|c: char| c == '=').unwrap();
+        let equ_index = line
+            .find('=')
+            .ok_or_else(|| ParseError::at_end(line, "'='").within(input, line))?;
         let node = line[..equ_index].trim().to_string();
-        if node.chars().last().unwrap() == 'A' {
+        if node.ends_with('A') {
             a_nodes.push(node.clone());
         }
 
-        let left_par_index = line.find(|c: char| c == '(').unwrap();
-        let right_par_index = line.find(|c: char| c == ')').unwrap();
-        let comma_index = line.find(|c: char| c == ',').unwrap();
+        let left_par_index = line
+            .find('(')
+            .ok_or_else(|| ParseError::at_end(line, "'('").within(input, line))?;
+        let comma_index = line
+            .find(',')
+            .ok_or_else(|| ParseError::at_end(line, "','").within(input, line))?;
+        let right_par_index = line
+            .find(')')
+            .ok_or_else(|| ParseError::at_end(line, "')'").within(input, line))?;
         let left = line[left_par_index + 1..comma_index].to_string();
         let right = line[comma_index + 1..right_par_index].trim().to_string();
 
         map.insert(node, (left, right));
     }
 
-    (instructions, map, a_nodes)
+    Ok((instructions, map, a_nodes))
 }
 
 #[derive(Debug, PartialEq)]
@@ -254,6 +270,16 @@ mod tests {
         assert_eq!(a_nodes[0], "AAA".to_string());
     }
 
+    #[test]
+    fn test_read_map_errors() {
+        let e = try_read_map("LXR\n\nAAA = (BBB, BBB)").unwrap_err();
+        assert_eq!((e.line(), e.column()), (1, 2));
+
+        let e = try_read_map("LR\n\nAAA = (BBB, BBB)\nBBB = (AAA ZZZ)").unwrap_err();
+        assert_eq!((e.line(), e.column()), (4, 16));
+        assert_eq!(e.expected(), "','");
+    }
+
     #[test]
     fn test_count_steps_from_a_to_z() {
         let (instructions, map, a_nodes) = read_map(
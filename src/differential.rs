@@ -0,0 +1,500 @@
+//! Differential testing: random inputs are fed to a reference and an
+//! optimized implementation of the same computation, and any input on which
+//! they disagree is shrunk to a minimal failing one.
+
+use crate::desert::{count_steps_from_a_to_z, count_steps_from_a_to_z2, read_map};
+use crate::lavaduct::{dig, dug_volume, read_dig_plan};
+use crate::race;
+use crate::rocks::Dish;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+/// A randomly generated test case for a pair of implementations.
+pub trait Case: Clone + fmt::Debug + Sized {
+    fn generate(rng: &mut StdRng) -> Self;
+
+    /// Returns smaller variants of this case, the most aggressive first.
+    fn shrink(&self) -> Vec<Self>;
+
+    /// Runs both implementations, returning a description of the mismatch
+    /// if they disagree.
+    fn check(&self) -> Result<(), String>;
+}
+
+/// A case on which both implementations disagree.
+#[derive(Clone, Debug)]
+pub struct Failure<C> {
+    pub seed: u64,
+    pub original: C,
+    pub shrunk: C,
+    pub message: String,
+}
+
+impl<C: fmt::Debug> fmt::Display for Failure<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "mismatch with seed {}: {}\nshrunk case: {:?}",
+            self.seed, self.message, self.shrunk
+        )
+    }
+}
+
+/// Checks `num_cases` random cases generated from `seed`.
+pub fn check<C: Case>(num_cases: usize, seed: u64) -> Result<(), Failure<C>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..num_cases {
+        let case = C::generate(&mut rng);
+        if let Err(message) = run(&case) {
+            let (shrunk, message) = shrink(case.clone(), message);
+            return Err(Failure {
+                seed,
+                original: case,
+                shrunk,
+                message,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Shrinks a failing case as long as one of its smaller variants still fails.
+pub fn shrink<C: Case>(case: C, message: String) -> (C, String) {
+    let mut case = case;
+    let mut message = message;
+    'shrinking: loop {
+        for candidate in case.shrink() {
+            if let Err(m) = run(&candidate) {
+                case = candidate;
+                message = m;
+                continue 'shrinking;
+            }
+        }
+        return (case, message);
+    }
+}
+
+/// Runs a check, a panic counting as a mismatch.
+fn run<C: Case>(case: &C) -> Result<(), String> {
+    match panic::catch_unwind(AssertUnwindSafe(|| case.check())) {
+        Ok(result) => result,
+        Err(e) => Err(match e.downcast_ref::<&str>() {
+            Some(s) => format!("panicked: {}", s),
+            None => match e.downcast_ref::<String>() {
+                Some(s) => format!("panicked: {}", s),
+                None => "panicked".to_string(),
+            },
+        }),
+    }
+}
+
+fn compare<T: PartialEq + fmt::Debug>(reference: T, optimized: T) -> Result<(), String> {
+    if reference == optimized {
+        Ok(())
+    } else {
+        Err(format!(
+            "reference gives {:?}, optimized gives {:?}",
+            reference, optimized
+        ))
+    }
+}
+
+/// `Dish::load_after_cycles` against `Dish::load_after_cycles_fast`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DishCase {
+    pub rows: Vec<String>,
+    pub cycles: u64,
+}
+
+impl Case for DishCase {
+    fn generate(rng: &mut StdRng) -> DishCase {
+        let num_rows = rng.gen_range(1..=8);
+        let num_cols = rng.gen_range(1..=8);
+        let rows = (0..num_rows)
+            .map(|_| {
+                (0..num_cols)
+                    .map(|_| match rng.gen_range(0..10) {
+                        0..=2 => 'O',
+                        3..=4 => '#',
+                        _ => '.',
+                    })
+                    .collect()
+            })
+            .collect();
+        DishCase {
+            rows,
+            cycles: rng.gen_range(1..=40),
+        }
+    }
+
+    fn shrink(&self) -> Vec<DishCase> {
+        let mut candidates = Vec::new();
+        if self.cycles > 1 {
+            candidates.push(DishCase {
+                cycles: self.cycles / 2,
+                ..self.clone()
+            });
+            candidates.push(DishCase {
+                cycles: self.cycles - 1,
+                ..self.clone()
+            });
+        }
+        if self.rows.len() > 1 {
+            for i in 0..self.rows.len() {
+                let mut rows = self.rows.clone();
+                rows.remove(i);
+                candidates.push(DishCase { rows, ..*self });
+            }
+        }
+        if self.rows[0].len() > 1 {
+            for j in 0..self.rows[0].len() {
+                let rows = self
+                    .rows
+                    .iter()
+                    .map(|row| {
+                        let mut row = row.clone();
+                        row.remove(j);
+                        row
+                    })
+                    .collect();
+                candidates.push(DishCase { rows, ..*self });
+            }
+        }
+        for (i, row) in self.rows.iter().enumerate() {
+            for (j, c) in row.char_indices() {
+                if c != '.' {
+                    let mut rows = self.rows.clone();
+                    rows[i].replace_range(j..j + 1, ".");
+                    candidates.push(DishCase { rows, ..*self });
+                }
+            }
+        }
+        candidates
+    }
+
+    fn check(&self) -> Result<(), String> {
+        let d = Dish::parse(&self.rows.join("\n"));
+        compare(
+            d.load_after_cycles(self.cycles),
+            d.load_after_cycles_fast(self.cycles),
+        )
+    }
+}
+
+/// `dig(..).volume()` against `dug_volume` on a lagoon whose top and bottom
+/// borders are skylines: each block of columns has its own width, height
+/// above the first row and depth below it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LagoonCase {
+    pub blocks: Vec<(u32, u32, u32)>,
+}
+
+impl LagoonCase {
+    /// Returns the dig plan, following the border clockwise.
+    pub fn plan(&self) -> String {
+        let mut moves: Vec<(char, u32)> = Vec::new();
+        let mut push = |direction: char, steps: u32| {
+            if steps == 0 {
+                return;
+            }
+            match moves.last_mut() {
+                Some((last, n)) if *last == direction => *n += steps,
+                _ => moves.push((direction, steps)),
+            }
+        };
+
+        // top border, from west to east
+        let mut height = 0;
+        for (width, h, _) in &self.blocks {
+            if *h > height {
+                push('U', h - height);
+            } else {
+                push('D', height - h);
+            }
+            push('R', *width);
+            height = *h;
+        }
+        push('D', height);
+
+        // bottom border, from east to west
+        let mut depth = 0;
+        for (width, _, d) in self.blocks.iter().rev() {
+            if *d > depth {
+                push('D', d - depth);
+            } else {
+                push('U', depth - d);
+            }
+            push('L', *width);
+            depth = *d;
+        }
+        push('U', depth);
+
+        moves
+            .iter()
+            .map(|(direction, steps)| format!("{} {} (#000000)", direction, steps))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl Case for LagoonCase {
+    fn generate(rng: &mut StdRng) -> LagoonCase {
+        let num_blocks = rng.gen_range(1..=6);
+        LagoonCase {
+            blocks: (0..num_blocks)
+                .map(|_| {
+                    (
+                        rng.gen_range(1..=6),
+                        rng.gen_range(1..=6),
+                        rng.gen_range(0..=6),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn shrink(&self) -> Vec<LagoonCase> {
+        let mut candidates = Vec::new();
+        if self.blocks.len() > 1 {
+            for i in 0..self.blocks.len() {
+                let mut blocks = self.blocks.clone();
+                blocks.remove(i);
+                candidates.push(LagoonCase { blocks });
+            }
+        }
+        for i in 0..self.blocks.len() {
+            let (width, height, depth) = self.blocks[i];
+            for smaller in [
+                (width - 1, height, depth),
+                (width, height - 1, depth),
+                (width, height, depth.saturating_sub(1)),
+            ] {
+                if smaller.0 > 0 && smaller.1 > 0 && smaller != self.blocks[i] {
+                    let mut blocks = self.blocks.clone();
+                    blocks[i] = smaller;
+                    candidates.push(LagoonCase { blocks });
+                }
+            }
+        }
+        candidates
+    }
+
+    fn check(&self) -> Result<(), String> {
+        let plan = read_dig_plan(&self.plan());
+        compare(dig(&plan).volume(), dug_volume(&plan))
+    }
+}
+
+/// `race::range` against `race::range_safer`, on races that can be won and
+/// whose record is not zero.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RaceCase {
+    pub time: u64,
+    pub distance: u64,
+}
+
+impl RaceCase {
+    fn is_valid(&self) -> bool {
+        self.distance > 0 && self.distance < self.time * self.time / 4
+    }
+}
+
+impl Case for RaceCase {
+    fn generate(rng: &mut StdRng) -> RaceCase {
+        let time = match rng.gen_range(0..3) {
+            0 => rng.gen_range(3..100),
+            1 => rng.gen_range(3..100_000),
+            _ => rng.gen_range(3..100_000_000),
+        };
+        RaceCase {
+            time,
+            distance: rng.gen_range(1..time * time / 4),
+        }
+    }
+
+    fn shrink(&self) -> Vec<RaceCase> {
+        [
+            RaceCase {
+                time: self.time / 2,
+                distance: self
+                    .distance
+                    .min(((self.time / 2).pow(2) / 4).saturating_sub(1)),
+            },
+            RaceCase {
+                time: self.time - 1,
+                distance: self
+                    .distance
+                    .min(((self.time - 1).pow(2) / 4).saturating_sub(1)),
+            },
+            RaceCase {
+                time: self.time,
+                distance: self.distance / 2,
+            },
+            RaceCase {
+                time: self.time,
+                distance: self.distance.saturating_sub(1),
+            },
+        ]
+        .into_iter()
+        .filter(|c| c.time >= 3 && c.is_valid() && c != self)
+        .collect()
+    }
+
+    fn check(&self) -> Result<(), String> {
+        compare(
+            race::range(self.time, self.distance),
+            race::range_safer(self.time, self.distance),
+        )
+    }
+}
+
+/// `count_steps_from_a_to_z` against `count_steps_from_a_to_z2`.
+///
+/// Each ghost walks a loop that goes back to its `Z` node after as many steps
+/// as it took to reach it from its `A` node, the structure the least common
+/// multiple shortcut relies on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GhostCase {
+    pub instructions: String,
+    pub loops: Vec<u32>,
+}
+
+impl GhostCase {
+    /// Returns the puzzle input: instructions, then the network.
+    pub fn network(&self) -> String {
+        let mut lines = vec![self.instructions.clone(), String::new()];
+        for (g, length) in self.loops.iter().enumerate() {
+            let node = |step: u32| match step {
+                0 => format!("{}AA", g),
+                s if s == *length => format!("{}ZZ", g),
+                s => format!("{}{:02}", g, s),
+            };
+            for step in 0..*length {
+                let next = node(step + 1);
+                lines.push(format!("{} = ({}, {})", node(step), next, next));
+            }
+            let next = node(1);
+            lines.push(format!("{} = ({}, {})", node(*length), next, next));
+        }
+        lines.join("\n")
+    }
+}
+
+impl Case for GhostCase {
+    fn generate(rng: &mut StdRng) -> GhostCase {
+        let num_instructions = rng.gen_range(1..=6);
+        let num_ghosts = rng.gen_range(1..=4);
+        GhostCase {
+            instructions: (0..num_instructions)
+                .map(|_| if rng.gen_bool(0.5) { 'L' } else { 'R' })
+                .collect(),
+            loops: (0..num_ghosts).map(|_| rng.gen_range(1..=12)).collect(),
+        }
+    }
+
+    fn shrink(&self) -> Vec<GhostCase> {
+        let mut candidates = Vec::new();
+        if self.loops.len() > 1 {
+            for i in 0..self.loops.len() {
+                let mut loops = self.loops.clone();
+                loops.remove(i);
+                candidates.push(GhostCase {
+                    loops,
+                    ..self.clone()
+                });
+            }
+        }
+        for i in 0..self.loops.len() {
+            if self.loops[i] > 1 {
+                let mut loops = self.loops.clone();
+                loops[i] -= 1;
+                candidates.push(GhostCase {
+                    loops,
+                    ..self.clone()
+                });
+            }
+        }
+        if self.instructions.len() > 1 {
+            candidates.push(GhostCase {
+                instructions: self.instructions[1..].to_string(),
+                ..self.clone()
+            });
+        }
+        candidates
+    }
+
+    fn check(&self) -> Result<(), String> {
+        let (instructions, map, a_nodes) = read_map(&self.network());
+        compare(
+            count_steps_from_a_to_z2(&instructions, &map, &a_nodes),
+            count_steps_from_a_to_z(&instructions, &map, &a_nodes),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Threshold(u32);
+
+    impl Case for Threshold {
+        fn generate(rng: &mut StdRng) -> Threshold {
+            Threshold(rng.gen_range(0..1000))
+        }
+
+        fn shrink(&self) -> Vec<Threshold> {
+            vec![Threshold(self.0 / 2), Threshold(self.0.saturating_sub(1))]
+        }
+
+        fn check(&self) -> Result<(), String> {
+            if self.0 > 10 {
+                Err(format!("{} is too large", self.0))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[test]
+    fn test_shrink_to_minimal_case() {
+        let failure = check::<Threshold>(100, 1).unwrap_err();
+        assert_eq!(failure.shrunk, Threshold(11));
+        assert_eq!(failure.message, "11 is too large");
+    }
+
+    #[test]
+    fn test_lagoon_plan() {
+        let case = LagoonCase {
+            blocks: vec![(2, 1, 0), (1, 2, 1)],
+        };
+        assert_eq!(
+            case.plan(),
+            "U 1 (#000000)\nR 2 (#000000)\nU 1 (#000000)\nR 1 (#000000)\nD 3 (#000000)\n\
+             L 1 (#000000)\nU 1 (#000000)\nL 2 (#000000)"
+        );
+    }
+
+    #[test]
+    fn test_dish() {
+        check::<DishCase>(200, 14).unwrap_or_else(|f| panic!("{}", f));
+    }
+
+    #[test]
+    fn test_lagoon() {
+        check::<LagoonCase>(200, 18).unwrap_or_else(|f| panic!("{}", f));
+    }
+
+    #[test]
+    fn test_race() {
+        check::<RaceCase>(1000, 6).unwrap_or_else(|f| panic!("{}", f));
+    }
+
+    #[test]
+    fn test_ghosts() {
+        check::<GhostCase>(200, 8).unwrap_or_else(|f| panic!("{}", f));
+    }
+}
@@ -1,29 +1,124 @@
-struct Number {
+use crate::error::{parse_token, ParseError};
+use crate::grid2d::Grid;
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+/// A number of a schematic, spanning columns `column().0` to `column().1`
+/// excluded.
+#[derive(Debug, PartialEq)]
+pub struct Number {
     line: usize,
     column: (usize, usize),
     value: u32,
 }
 
-struct Symbol {
+impl Number {
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> (usize, usize) {
+        self.column
+    }
+
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Symbol {
     line: usize,
     column: usize,
     value: char,
 }
 
+impl Symbol {
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn value(&self) -> char {
+        self.value
+    }
+}
+
+// index of the token covering a cell
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Cell {
+    Number(usize),
+    Symbol(usize),
+}
+
+/// Tells which symbols are gears, and what their ratios are: a gear is one
+/// of `symbols` adjacent to exactly `num_numbers` numbers, and its ratio
+/// combines their values.
+#[derive(Clone)]
+pub struct GearRule {
+    symbols: Vec<char>,
+    num_numbers: usize,
+    combine: fn(&[u32]) -> u32,
+}
+
+impl GearRule {
+    pub fn new(symbols: &str, num_numbers: usize, combine: fn(&[u32]) -> u32) -> GearRule {
+        GearRule {
+            symbols: symbols.chars().collect(),
+            num_numbers,
+            combine,
+        }
+    }
+
+    /// The rule of the puzzle: a `*` next to two numbers, multiplied.
+    pub fn standard() -> GearRule {
+        GearRule::new("*", 2, |values| values.iter().product())
+    }
+}
+
+/// Numbers and symbols connected to each other through adjacent tokens.
+#[derive(Debug, PartialEq)]
+pub struct Component<'a> {
+    pub numbers: Vec<&'a Number>,
+    pub symbols: Vec<&'a Symbol>,
+}
+
+/// How `EngineSchematic::render` marks numbers and gears.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Style {
+    /// Part numbers in green, other numbers in red and gears in bold yellow.
+    Ansi,
+    /// `[467]` for part numbers, `(114)` for other numbers and `{*}` for
+    /// gears.
+    Brackets,
+}
+
 pub struct EngineSchematic {
     numbers: Vec<Number>,
     symbols: Vec<Symbol>,
+    cells: Grid<Option<Cell>>,
 }
 
 impl EngineSchematic {
     pub fn parse(input: &str) -> EngineSchematic {
-        let p = Parser::new(input);
+        EngineSchematic::try_parse(input).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_parse(input: &str) -> Result<EngineSchematic, ParseError> {
         let mut e = EngineSchematic {
             numbers: Vec::new(),
             symbols: Vec::new(),
+            cells: Grid::new(0, 0, None),
         };
 
-        for token in p.tokens() {
+        let (mut num_rows, mut num_cols) = (0, 0);
+        for token in Tokenizer::new(input) {
+            let token = token?;
+            num_rows = num_rows.max(token.line() + 1);
+            num_cols = num_cols.max(token.column().1);
             match token.value() {
                 TokenValue::Number(n) => e.numbers.push(Number {
                     line: token.line(),
@@ -38,7 +133,93 @@ impl EngineSchematic {
             }
         }
 
-        e
+        // keep the dimensions of the input, trailing dots included
+        num_rows = num_rows.max(input.lines().count());
+        num_cols = num_cols.max(
+            input
+                .lines()
+                .map(|line| line.chars().count())
+                .max()
+                .unwrap_or(0),
+        );
+        e.cells = Grid::new(num_rows, num_cols, None);
+        for (i, number) in e.numbers.iter().enumerate() {
+            for column in number.column.0..number.column.1 {
+                e.cells[(number.line, column)] = Some(Cell::Number(i));
+            }
+        }
+        for (i, symbol) in e.symbols.iter().enumerate() {
+            e.cells[(symbol.line, symbol.column)] = Some(Cell::Symbol(i));
+        }
+
+        Ok(e)
+    }
+
+    /// Returns the number of lines and columns of the schematic.
+    pub fn size(&self) -> (usize, usize) {
+        self.cells.size()
+    }
+
+    /// Renders the schematic with its numbers marked as part numbers or
+    /// not, and its gears, as defined by `rule`, marked too. Short lines are
+    /// padded with `.`.
+    pub fn render(&self, style: Style, rule: &GearRule) -> String {
+        let (part, other, gear, end) = match style {
+            Style::Ansi => ("\x1b[32m", "\x1b[31m", "\x1b[1;33m", "\x1b[0m"),
+            Style::Brackets => ("[", "(", "{", ""),
+        };
+        let closing = |open: &str| match (style, open) {
+            (Style::Ansi, _) => end,
+            (Style::Brackets, "[") => "]",
+            (Style::Brackets, "(") => ")",
+            (Style::Brackets, _) => "}",
+        };
+
+        let mut text = String::new();
+        for line in 0..self.cells.num_rows() {
+            if line > 0 {
+                text.push('\n');
+            }
+            let mut column = 0;
+            while column < self.cells.num_cols() {
+                match self.cells[(line, column)] {
+                    None => {
+                        text.push('.');
+                        column += 1;
+                    }
+                    Some(Cell::Number(i)) => {
+                        let number = &self.numbers[i];
+                        let open = if self.is_part_number(number) {
+                            part
+                        } else {
+                            other
+                        };
+                        write!(text, "{}{}{}", open, number.value, closing(open)).unwrap();
+                        column = number.column.1;
+                    }
+                    Some(Cell::Symbol(i)) => {
+                        let symbol = &self.symbols[i];
+                        if self.gear_ratio(symbol, rule).is_some() {
+                            write!(text, "{}{}{}", gear, symbol.value, closing(gear)).unwrap();
+                        } else {
+                            text.push(symbol.value);
+                        }
+                        column += 1;
+                    }
+                }
+            }
+        }
+        text
+    }
+
+    /// Returns the numbers, in reading order.
+    pub fn numbers(&self) -> &[Number] {
+        &self.numbers
+    }
+
+    /// Returns the symbols, in reading order.
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
     }
 
     pub fn part_numbers(&self) -> impl Iterator<Item = u32> + '_ {
@@ -49,52 +230,126 @@ impl EngineSchematic {
     }
 
     pub fn gear_ratios(&self) -> impl Iterator<Item = u32> + '_ {
-        self.symbols.iter().filter_map(|s| self.gear_ratio(s))
+        self.gear_ratios_with(GearRule::standard())
+    }
+
+    /// Iterates over the ratios of the gears following `rule`.
+    pub fn gear_ratios_with(&self, rule: GearRule) -> impl Iterator<Item = u32> + '_ {
+        self.symbols
+            .iter()
+            .filter_map(move |s| self.gear_ratio(s, &rule))
+    }
+
+    pub fn is_part_number(&self, number: &Number) -> bool {
+        self.neighbours(number.line, number.column)
+            .any(|cell| matches!(cell, Cell::Symbol(_)))
+    }
+
+    /// Returns the numbers adjacent to `symbol`, diagonals included, in
+    /// reading order.
+    pub fn numbers_adjacent_to(&self, symbol: &Symbol) -> Vec<&Number> {
+        let mut ids: Vec<usize> = self
+            .neighbours(symbol.line, (symbol.column, symbol.column + 1))
+            .filter_map(|cell| match cell {
+                Cell::Number(i) => Some(i),
+                Cell::Symbol(_) => None,
+            })
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids.into_iter().map(|i| &self.numbers[i]).collect()
     }
 
-    fn is_part_number(&self, number: &Number) -> bool {
-        for symbol in &self.symbols {
-            if number.line > 1 && symbol.line < number.line - 1 {
+    /// Returns the symbols adjacent to `number`, diagonals included, in
+    /// reading order.
+    pub fn symbols_adjacent_to(&self, number: &Number) -> Vec<&Symbol> {
+        let mut ids: Vec<usize> = self
+            .neighbours(number.line, number.column)
+            .filter_map(|cell| match cell {
+                Cell::Symbol(i) => Some(i),
+                Cell::Number(_) => None,
+            })
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids.into_iter().map(|i| &self.symbols[i]).collect()
+    }
+
+    /// Groups the numbers and symbols that are adjacent, directly or through
+    /// other tokens. Components are in the reading order of their first
+    /// token.
+    pub fn components(&self) -> Vec<Component<'_>> {
+        let mut seen: Grid<bool> = Grid::new(self.cells.num_rows(), self.cells.num_cols(), false);
+        let mut components: Vec<Component> = Vec::new();
+
+        for (position, cell) in self.cells.iter() {
+            if cell.is_none() || seen[position] {
                 continue;
             }
-            if symbol.line > number.line + 1 {
-                break;
-            }
 
-            if (number.column.0 <= 1 || symbol.column >= number.column.0 - 1)
-                && symbol.column <= number.column.1
-            {
-                return true;
+            let mut component = Component {
+                numbers: Vec::new(),
+                symbols: Vec::new(),
+            };
+            let mut queue: VecDeque<Cell> = VecDeque::from([cell.unwrap()]);
+            while let Some(cell) = queue.pop_front() {
+                let (line, columns) = self.span(cell);
+                if seen[(line, columns.0)] {
+                    continue;
+                }
+                for column in columns.0..columns.1 {
+                    seen[(line, column)] = true;
+                }
+                match cell {
+                    Cell::Number(i) => component.numbers.push(&self.numbers[i]),
+                    Cell::Symbol(i) => component.symbols.push(&self.symbols[i]),
+                }
+                queue.extend(self.neighbours(line, columns));
             }
+
+            component.numbers.sort_by_key(|n| (n.line, n.column.0));
+            component.symbols.sort_by_key(|s| (s.line, s.column));
+            components.push(component);
         }
-        false
+
+        components
     }
 
-    fn gear_ratio(&self, symbol: &Symbol) -> Option<u32> {
-        if symbol.value != '*' {
-            return None;
+    // line and columns of the token of a cell
+    fn span(&self, cell: Cell) -> (usize, (usize, usize)) {
+        match cell {
+            Cell::Number(i) => (self.numbers[i].line, self.numbers[i].column),
+            Cell::Symbol(i) => {
+                let symbol = &self.symbols[i];
+                (symbol.line, (symbol.column, symbol.column + 1))
+            }
         }
+    }
 
-        let mut numbers: Vec<&Number> = Vec::new();
-        for number in &self.numbers {
-            if symbol.line > 1 && number.line < symbol.line - 1 {
-                continue;
-            }
-            if number.line > symbol.line + 1 {
-                break;
-            }
+    /// Iterates over the tokens around the cells of `line` from `columns.0`
+    /// to `columns.1` excluded, diagonals included.
+    fn neighbours(&self, line: usize, columns: (usize, usize)) -> impl Iterator<Item = Cell> + '_ {
+        (line.saturating_sub(1)..=line + 1)
+            .flat_map(move |l| {
+                (columns.0.saturating_sub(1)..=columns.1)
+                    .filter(move |c| l != line || *c < columns.0 || *c >= columns.1)
+                    .map(move |c| (l, c))
+            })
+            .filter_map(|(l, c)| *self.cells.get(l, c)?)
+    }
 
-            if (number.column.0 <= 1 || symbol.column >= number.column.0 - 1)
-                && symbol.column <= number.column.1
-            {
-                numbers.push(&number);
-            }
+    fn gear_ratio(&self, symbol: &Symbol, rule: &GearRule) -> Option<u32> {
+        if !rule.symbols.contains(&symbol.value) {
+            return None;
         }
-        if numbers.len() != 2 {
+
+        let numbers = self.numbers_adjacent_to(symbol);
+        if numbers.len() != rule.num_numbers {
             return None;
         }
 
-        Some(numbers[0].value * numbers[1].value)
+        let values: Vec<u32> = numbers.iter().map(|n| n.value).collect();
+        Some((rule.combine)(&values))
     }
 }
 
@@ -123,49 +378,89 @@ impl Token {
     }
 }
 
-pub struct Parser {
-    tokens: Vec<Token>,
+/// Lazily splits a schematic into tokens, lines and columns starting at 0.
+/// Columns count characters, not bytes.
+///
+/// Lines end with `\n` or `\r\n`. The iteration stops after an error.
+pub struct Tokenizer<'a> {
+    text: &'a str,
+    rest: &'a str,
+    line: usize,
+    column: usize,
 }
 
-impl Parser {
-    pub fn new(input: &str) -> Parser {
-        let mut p = Parser { tokens: Vec::new() };
-        p.parse(input, 0, 0);
-        p
+impl<'a> Tokenizer<'a> {
+    pub fn new(text: &'a str) -> Tokenizer<'a> {
+        Tokenizer {
+            text,
+            rest: text,
+            line: 0,
+            column: 0,
+        }
     }
+}
 
-    fn parse(&mut self, input: &str, line: usize, column: usize) {
-        if let Some(start) = input.find(|c: char| c != '.') {
+impl Iterator for Tokenizer<'_> {
+    type Item = Result<Token, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let input = self.rest;
+            let start = input.find(|c: char| c != '.')?;
             let c = input[start..].chars().next().unwrap();
 
-            if c == '\n' {
-                self.parse(&input[start + 1..], line + 1, 0);
-            } else if c.is_digit(10) {
+            if c == '\n' || (c == '\r' && input[start + 1..].starts_with('\n')) {
+                self.rest = &input[start + c.len_utf8() + (c == '\r') as usize..];
+                self.line += 1;
+                self.column = 0;
+            } else if c.is_ascii_digit() {
                 let end = start
-                    + match input[start..].find(|c: char| !c.is_digit(10)) {
+                    + match input[start..].find(|c: char| !c.is_ascii_digit()) {
                         Some(i) => i,
                         None => input[start..].len(),
                     };
-                let num: u32 = input[start..end].parse().unwrap();
-                self.tokens.push(Token {
-                    line: line,
-                    column: (column + start, column + end),
-                    value: TokenValue::Number(num),
-                });
-                self.parse(&input[end..], line, column + end);
+                let token =
+                    parse_token(self.text, &input[start..end], "number below 2^32").map(|num| {
+                        Token {
+                            line: self.line,
+                            column: (self.column + start, self.column + end),
+                            value: TokenValue::Number(num),
+                        }
+                    });
+                self.rest = if token.is_ok() { &input[end..] } else { "" };
+                self.column += end;
+                return Some(token);
             } else {
-                self.tokens.push(Token {
-                    line: line,
-                    column: (column + start, column + start + 1),
+                let token = Token {
+                    line: self.line,
+                    column: (self.column + start, self.column + start + 1),
                     value: TokenValue::Symbol(c),
-                });
-                self.parse(&input[start + 1..], line, column + start + 1);
+                };
+                self.rest = &input[start + c.len_utf8()..];
+                self.column += start + 1;
+                return Some(Ok(token));
             }
         }
     }
+}
+
+pub struct Parser {
+    tokens: Vec<Token>,
+}
+
+impl Parser {
+    pub fn new(input: &str) -> Parser {
+        Parser::try_new(input).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(input: &str) -> Result<Parser, ParseError> {
+        Ok(Parser {
+            tokens: Tokenizer::new(input).collect::<Result<Vec<Token>, ParseError>>()?,
+        })
+    }
 
     pub fn tokens(&self) -> impl Iterator<Item = &Token> {
-        self.tokens.as_slice().into_iter()
+        self.tokens.iter()
     }
 }
 
@@ -260,6 +555,73 @@ mod tests {
         assert!(tokens.next().is_none());
     }
 
+    #[test]
+    fn test_tokenizer_crlf() {
+        let tokens: Vec<Token> = Tokenizer::new("467.\r\n..*.\r\n\r\n.5")
+            .map(|t| t.unwrap())
+            .collect();
+        let layout: Vec<(usize, (usize, usize))> =
+            tokens.iter().map(|t| (t.line(), t.column())).collect();
+        assert_eq!(layout, vec![(0, (0, 3)), (1, (2, 3)), (3, (1, 2))]);
+        assert_eq!(tokens[1].value(), &TokenValue::Symbol('*'));
+
+        // a lone carriage return is a symbol
+        let tokens: Vec<Token> = Tokenizer::new("1\r.").map(|t| t.unwrap()).collect();
+        assert_eq!(tokens[1].value(), &TokenValue::Symbol('\r'));
+    }
+
+    #[test]
+    fn test_tokenizer_non_ascii_symbols() {
+        let tokens: Vec<Token> = Tokenizer::new("€12.é\n.....7")
+            .map(|t| t.unwrap())
+            .collect();
+        let layout: Vec<(usize, (usize, usize))> =
+            tokens.iter().map(|t| (t.line(), t.column())).collect();
+        assert_eq!(
+            layout,
+            vec![(0, (0, 1)), (0, (1, 3)), (0, (4, 5)), (1, (5, 6))]
+        );
+
+        let e = EngineSchematic::parse("..€.\n.12.7\n.....");
+        assert_eq!(e.size(), (3, 5));
+        assert_eq!(e.part_numbers().collect::<Vec<u32>>(), vec![12]);
+        assert_eq!(
+            e.render(Style::Brackets, &GearRule::standard()),
+            "..€..\n.[12].(7)\n....."
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_is_lazy() {
+        let mut tokens = Tokenizer::new("1.2\n99999999999.3");
+        assert_eq!(
+            tokens.next().unwrap().unwrap().value(),
+            &TokenValue::Number(1)
+        );
+        assert_eq!(
+            tokens.next().unwrap().unwrap().value(),
+            &TokenValue::Number(2)
+        );
+        assert!(tokens.next().unwrap().is_err());
+        assert!(tokens.next().is_none());
+    }
+
+    #[test]
+    fn test_large_schematic() {
+        let input = "1*.\n".repeat(200_000);
+        assert_eq!(Parser::new(&input).tokens().count(), 400_000);
+        assert_eq!(
+            EngineSchematic::parse(&input).part_numbers().count(),
+            200_000
+        );
+    }
+
+    #[test]
+    fn test_parse_number_too_large() {
+        let e = Parser::try_new("..1..\n.99999999999.").err().unwrap();
+        assert_eq!((e.line(), e.column()), (2, 2));
+    }
+
     #[test]
     fn test_no_part_number_if_empty_input() {
         let e = EngineSchematic::parse("");
@@ -292,6 +654,119 @@ mod tests {
         assert!(part_numbers.next().is_none());
     }
 
+    #[test]
+    fn test_uneven_lines() {
+        let e = EngineSchematic::parse("12\n..*34\n\n5.\n.#");
+        assert_eq!(e.part_numbers().collect::<Vec<u32>>(), vec![12, 34, 5]);
+        assert_eq!(e.gear_ratios().collect::<Vec<u32>>(), vec![12 * 34]);
+    }
+
+    #[test]
+    fn test_gear_ratios_crlf() {
+        let e = EngineSchematic::parse("467..114..\r\n...*......\r\n..35..633.\r\n");
+        assert_eq!(e.gear_ratios().collect::<Vec<u32>>(), vec![467 * 35]);
+    }
+
+    const EXAMPLE: &str = "467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..";
+
+    #[test]
+    fn test_adjacency() {
+        let e = EngineSchematic::parse(EXAMPLE);
+        let star = &e.symbols()[0];
+        assert_eq!((star.line(), star.column(), star.value()), (1, 3, '*'));
+        let values: Vec<u32> = e
+            .numbers_adjacent_to(star)
+            .iter()
+            .map(|n| n.value())
+            .collect();
+        assert_eq!(values, vec![467, 35]);
+
+        let n617 = &e.numbers()[4];
+        assert_eq!(n617.value(), 617);
+        assert_eq!(e.symbols_adjacent_to(n617), vec![&e.symbols()[2]]);
+        assert!(e.symbols_adjacent_to(&e.numbers()[1]).is_empty());
+        assert!(!e.is_part_number(&e.numbers()[1]));
+    }
+
+    #[test]
+    fn test_components() {
+        let e = EngineSchematic::parse(EXAMPLE);
+        let components = e.components();
+        let values: Vec<Vec<u32>> = components
+            .iter()
+            .map(|c| c.numbers.iter().map(|n| n.value()).collect())
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                vec![467, 35],
+                vec![114],
+                vec![633],
+                vec![617],
+                vec![592],
+                vec![58],
+                vec![755, 598],
+                vec![664]
+            ]
+        );
+        assert_eq!(components[0].symbols.len(), 1);
+        assert!(components[1].symbols.is_empty());
+        assert_eq!(components[6].symbols[0].value(), '*');
+
+        // tokens chain through each other
+        let e = EngineSchematic::parse("1.2\n.*.\n..#3");
+        assert_eq!(e.components().len(), 1);
+    }
+
+    #[test]
+    fn test_render() {
+        let e = EngineSchematic::parse(EXAMPLE);
+        assert_eq!(e.size(), (10, 10));
+        assert_eq!(
+            e.render(Style::Brackets, &GearRule::standard()),
+            "[467]..(114)..
+...{*}......
+..[35]..[633].
+......#...
+[617]*......
+.....+.(58).
+..[592].....
+......[755].
+...$.{*}....
+.[664].[598].."
+        );
+
+        let e = EngineSchematic::parse("..\r\n1*....\r\n\r\n");
+        assert_eq!(e.size(), (3, 6));
+        assert_eq!(
+            e.render(Style::Ansi, &GearRule::standard()),
+            "......\n\x1b[32m1\x1b[0m*....\n......"
+        );
+    }
+
+    #[test]
+    fn test_gear_rules() {
+        let e = EngineSchematic::parse(EXAMPLE);
+        let sums: Vec<u32> = e
+            .gear_ratios_with(GearRule::new("*", 2, |v| v.iter().sum()))
+            .collect();
+        assert_eq!(sums, vec![467 + 35, 755 + 598]);
+
+        let singles: Vec<u32> = e
+            .gear_ratios_with(GearRule::new("#+$*", 1, |v| v[0]))
+            .collect();
+        assert_eq!(singles, vec![633, 617, 592, 664]);
+    }
+
     #[test]
     fn test_gear_ratios() {
         let e = EngineSchematic::parse(
@@ -311,4 +786,4 @@ mod tests {
         assert_eq!(gear_ratios.next(), Some(755 * 598));
         assert!(gear_ratios.next().is_none());
     }
-}
\ No newline at end of file
+}
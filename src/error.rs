@@ -0,0 +1,254 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Found {
+    Token(String),
+    EndOfLine,
+    EndOfInput,
+}
+
+impl fmt::Display for Found {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Found::Token(token) => write!(f, "'{}'", token),
+            Found::EndOfLine => write!(f, "end of line"),
+            Found::EndOfInput => write!(f, "end of input"),
+        }
+    }
+}
+
+/// Error returned when an input does not follow the expected format.
+///
+/// Lines and columns start at 1, columns are counted in characters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    line: usize,
+    column: usize,
+    expected: String,
+    found: Found,
+}
+
+impl ParseError {
+    pub fn new(line: usize, column: usize, expected: &str, found: Found) -> ParseError {
+        ParseError {
+            line,
+            column,
+            expected: expected.to_string(),
+            found,
+        }
+    }
+
+    /// Creates an error for `token`, which must be a slice of `text`.
+    ///
+    /// An empty token stands for the end of the line or of the text.
+    pub fn at(text: &str, token: &str, expected: &str) -> ParseError {
+        let offset = offset_of(text, token);
+        let (line, column) = line_and_column(text, offset);
+        let found = if !token.is_empty() {
+            Found::Token(token.to_string())
+        } else if offset == text.len() {
+            Found::EndOfInput
+        } else {
+            Found::EndOfLine
+        };
+        ParseError::new(line, column, expected, found)
+    }
+
+    /// Creates an error for a missing token at the end of `text`.
+    pub fn at_end(text: &str, expected: &str) -> ParseError {
+        ParseError::at(text, &text[text.len()..], expected)
+    }
+
+    /// Moves an error located in `part` to its position in `text`, `part`
+    /// being a slice of `text`.
+    pub fn within(self, text: &str, part: &str) -> ParseError {
+        let offset = offset_of(text, part);
+        let (line, column) = line_and_column(text, offset);
+
+        let found = match self.found {
+            Found::EndOfInput if offset + part.len() < text.len() => Found::EndOfLine,
+            found => found,
+        };
+        if self.line == 1 {
+            ParseError {
+                line,
+                column: column + self.column - 1,
+                found,
+                ..self
+            }
+        } else {
+            ParseError {
+                line: line + self.line - 1,
+                found,
+                ..self
+            }
+        }
+    }
+
+    /// Moves an error located in a single line to line `line` of a text,
+    /// `terminated` telling whether a line break follows it.
+    pub fn on_line(self, line: usize, terminated: bool) -> ParseError {
+        let found = match self.found {
+            Found::EndOfInput if terminated => Found::EndOfLine,
+            found => found,
+        };
+        ParseError {
+            line: line + self.line - 1,
+            found,
+            ..self
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn expected(&self) -> &str {
+        &self.expected
+    }
+
+    pub fn found(&self) -> &Found {
+        &self.found
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected {}, found {}",
+            self.line, self.column, self.expected, self.found
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// Error returned when reading an input from a stream.
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Io(e) => write!(f, "{}", e),
+            ReadError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for ReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReadError::Io(e) => Some(e),
+            ReadError::Parse(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> ReadError {
+        ReadError::Io(e)
+    }
+}
+
+impl From<ParseError> for ReadError {
+    fn from(e: ParseError) -> ReadError {
+        ReadError::Parse(e)
+    }
+}
+
+/// Parses `token`, a slice of `text`, reporting its position on failure.
+pub(crate) fn parse_token<T: FromStr>(
+    text: &str,
+    token: &str,
+    expected: &str,
+) -> Result<T, ParseError> {
+    token
+        .parse()
+        .map_err(|_| ParseError::at(text, token, expected))
+}
+
+/// Returns the byte offset of `part` in `text`, or the length of `text` when
+/// `part` is not one of its slices.
+fn offset_of(text: &str, part: &str) -> usize {
+    let start = text.as_ptr() as usize;
+    let position = part.as_ptr() as usize;
+    if position < start || position > start + text.len() {
+        return text.len();
+    }
+    position - start
+}
+
+fn line_and_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = match before.rfind('\n') {
+        Some(index) => index + 1,
+        None => 0,
+    };
+    (line, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_at_token() {
+        let text = "12 13\n14 x5 16";
+        let e = ParseError::at(text, &text[9..11], "number");
+        assert_eq!(e.line(), 2);
+        assert_eq!(e.column(), 4);
+        assert_eq!(e.found(), &Found::Token("x5".to_string()));
+        assert_eq!(
+            format!("{}", e),
+            "line 2, column 4: expected number, found 'x5'"
+        );
+    }
+
+    #[test]
+    fn test_error_at_end() {
+        let text = "12 13\n14";
+        let e = ParseError::at_end(text, "number");
+        assert_eq!((e.line(), e.column()), (2, 3));
+        assert_eq!(e.found(), &Found::EndOfInput);
+    }
+
+    #[test]
+    fn test_error_within_line() {
+        let text = "12 13\n14 x5 16";
+        let line = &text[6..];
+        let e = ParseError::at_end(&line[3..5], "number").within(text, &line[3..5]);
+        assert_eq!((e.line(), e.column()), (2, 6));
+        assert_eq!(e.found(), &Found::EndOfLine);
+    }
+
+    #[test]
+    fn test_error_on_line() {
+        let e = ParseError::at_end("14 x5", "number").on_line(3, true);
+        assert_eq!((e.line(), e.column()), (3, 6));
+        assert_eq!(e.found(), &Found::EndOfLine);
+
+        let e = ParseError::at_end("14 x5", "number").on_line(3, false);
+        assert_eq!(e.found(), &Found::EndOfInput);
+    }
+
+    #[test]
+    fn test_parse_token() {
+        let text = "12 1x";
+        assert_eq!(parse_token::<u32>(text, &text[..2], "number"), Ok(12));
+        let e = parse_token::<u32>(text, &text[3..], "number").unwrap_err();
+        assert_eq!((e.line(), e.column()), (1, 4));
+    }
+}
@@ -0,0 +1,801 @@
+//! Random puzzle inputs, for stress tests and benchmarks.
+//!
+//! Every generator takes a random number generator and a size, which is a
+//! number of lines or the side of a grid, and returns an input that the
+//! corresponding day parses. Inputs also follow the puzzle's guarantees
+//! when they are cheap to meet (closed clockwise loops, winnable races...).
+
+use crate::grid2d::{Direction, Point};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeMap, HashSet};
+
+/// Generates an input for `day`, or `None` if the day has no generator.
+pub fn generate(day: u32, seed: u64, size: usize) -> Option<String> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let rng = &mut rng;
+    let size = size.max(1);
+    let input = match day {
+        1 => calibration_document(rng, size),
+        2 => game_record(rng, size),
+        3 => engine_schematic(rng, size),
+        4 => scratchcards(rng, size),
+        5 => almanac(rng, size),
+        6 => race_sheet(rng, size),
+        7 => camel_bids(rng, size),
+        8 => desert_network(rng, size),
+        9 => oasis_report(rng, size),
+        10 => pipe_loop(rng, size),
+        11 => space_map(rng, size),
+        12 => condition_records(rng, size),
+        13 => mirror_notes(rng, size),
+        14 => dish(rng, size),
+        15 => init_sequence(rng, size),
+        16 => contraption(rng, size),
+        17 => loss_map(rng, size),
+        18 => dig_plan(rng, size),
+        22 => brick_snapshot(rng, size),
+        25 => wiring_diagram(rng, size),
+        _ => return None,
+    };
+    Some(input)
+}
+
+const WORDS: [&str; 10] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
+fn grid<F: FnMut(&mut StdRng) -> char>(rng: &mut StdRng, size: usize, mut cell: F) -> String {
+    (0..size)
+        .map(|_| (0..size).map(|_| cell(rng)).collect::<String>())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn lowercase(rng: &mut StdRng, len: usize) -> String {
+    (0..len).map(|_| rng.gen_range('a'..='z')).collect()
+}
+
+/// Lines of letters, digits and spelled digits, with at least one digit each.
+pub fn calibration_document(rng: &mut StdRng, size: usize) -> String {
+    let mut lines = Vec::with_capacity(size);
+    for _ in 0..size {
+        let mut chunks: Vec<String> = Vec::new();
+        for _ in 0..rng.gen_range(1..8) {
+            chunks.push(match rng.gen_range(0..3) {
+                0 => rng.gen_range('1'..='9').to_string(),
+                1 => WORDS[rng.gen_range(1..10)].to_string(),
+                _ => {
+                    let len = rng.gen_range(1..4);
+                    lowercase(rng, len)
+                }
+            });
+        }
+        let i = rng.gen_range(0..=chunks.len());
+        chunks.insert(i, rng.gen_range('1'..='9').to_string());
+        lines.push(chunks.concat());
+    }
+    lines.join("\n")
+}
+
+pub fn game_record(rng: &mut StdRng, size: usize) -> String {
+    let mut lines = Vec::with_capacity(size);
+    for id in 1..=size {
+        let draws: Vec<String> = (0..rng.gen_range(1..=6))
+            .map(|_| {
+                let mut colours = vec!["red", "green", "blue"];
+                colours.shuffle(rng);
+                colours.truncate(rng.gen_range(1..=3));
+                colours
+                    .iter()
+                    .map(|colour| format!("{} {}", rng.gen_range(1..=20), colour))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            })
+            .collect();
+        lines.push(format!("Game {}: {}", id, draws.join("; ")));
+    }
+    lines.join("\n")
+}
+
+pub fn engine_schematic(rng: &mut StdRng, size: usize) -> String {
+    let mut rows: Vec<Vec<char>> = vec![vec!['.'; size]; size];
+    for row in rows.iter_mut() {
+        let mut j = 0;
+        while j < size {
+            let len = rng.gen_range(1..=3);
+            if rng.gen_bool(0.2) && j + len <= size {
+                row[j] = rng.gen_range('1'..='9');
+                for cell in row.iter_mut().skip(j + 1).take(len - 1) {
+                    *cell = rng.gen_range('0'..='9');
+                }
+                j += len;
+            }
+            j += 1;
+        }
+    }
+    for row in rows.iter_mut() {
+        for cell in row.iter_mut() {
+            if *cell == '.' && rng.gen_bool(0.08) {
+                *cell = *b"*#+$/@=%&-".choose(rng).unwrap() as char;
+            }
+        }
+    }
+    rows.iter()
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Most cards, originals and copies, at the end of a generated cascade; real
+/// inputs end with millions of cards.
+const MAX_SCRATCHCARDS: u64 = 10_000_000;
+
+/// Cards with 10 winning and 25 held numbers, no card winning copies of
+/// cards past the last one. Matches are cut so that the cascade ends with at
+/// most `MAX_SCRATCHCARDS` cards, however many cards there are.
+pub fn scratchcards(rng: &mut StdRng, size: usize) -> String {
+    let format = |numbers: &[u32]| {
+        numbers
+            .iter()
+            .map(|n| format!("{:>2}", n))
+            .collect::<Vec<String>>()
+            .join(" ")
+    };
+
+    let mut copies = vec![1u64; size];
+    let mut total = size as u64;
+    let mut lines = Vec::with_capacity(size);
+    for id in 1..=size {
+        let mut numbers: Vec<u32> = (1..100).collect();
+        numbers.shuffle(rng);
+        let winning = &numbers[..10];
+        let won = copies[id - 1];
+        let num_matches = rng
+            .gen_range(0..=5)
+            .min(size - id)
+            .min((MAX_SCRATCHCARDS.saturating_sub(total) / won) as usize);
+        for card in copies[id..id + num_matches].iter_mut() {
+            *card += won;
+        }
+        total += num_matches as u64 * won;
+        let mut held: Vec<u32> = winning[..num_matches].to_vec();
+        held.extend_from_slice(&numbers[10..35 - num_matches]);
+        held.shuffle(rng);
+        lines.push(format!(
+            "Card {:>3}: {} | {}",
+            id,
+            format(winning),
+            format(&held)
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Returns `count` disjoint intervals (start, length) of `[0, limit)`,
+/// sorted by start.
+fn disjoint_intervals(rng: &mut StdRng, count: usize, limit: u64) -> Vec<(u64, u64)> {
+    let mut bounds: Vec<u64> = Vec::with_capacity(2 * count);
+    while bounds.len() < 2 * count {
+        let bound = rng.gen_range(0..=limit);
+        if !bounds.contains(&bound) {
+            bounds.push(bound);
+        }
+    }
+    bounds.sort();
+    bounds
+        .chunks(2)
+        .map(|pair| (pair[0], pair[1] - pair[0]))
+        .collect()
+}
+
+/// Seeds, then the seven maps from seed to location; sources and
+/// destinations of a map never overlap.
+pub fn almanac(rng: &mut StdRng, size: usize) -> String {
+    const LIMIT: u64 = 1 << 32;
+    const NAMES: [&str; 8] = [
+        "seed",
+        "soil",
+        "fertilizer",
+        "water",
+        "light",
+        "temperature",
+        "humidity",
+        "location",
+    ];
+
+    let seeds: Vec<String> = disjoint_intervals(rng, size, LIMIT)
+        .iter()
+        .map(|(start, len)| format!("{} {}", start, len.max(&1)))
+        .collect();
+    let mut blocks = vec![format!("seeds: {}", seeds.join(" "))];
+
+    for names in NAMES.windows(2) {
+        let count = rng.gen_range(1..=size + 1);
+        let sources = disjoint_intervals(rng, count, LIMIT);
+        let total: u64 = sources.iter().map(|(_, len)| len).sum();
+        let mut order: Vec<usize> = (0..sources.len()).collect();
+        order.shuffle(rng);
+
+        let mut destinations = vec![0u64; sources.len()];
+        let mut cursor = 0u64;
+        for i in order {
+            cursor += rng.gen_range(0..=(LIMIT - total) / sources.len() as u64);
+            destinations[i] = cursor;
+            cursor += sources[i].1;
+        }
+
+        let mut lines = vec![format!("{}-to-{} map:", names[0], names[1])];
+        let mut ranges: Vec<String> = sources
+            .iter()
+            .zip(destinations)
+            .filter(|((_, len), _)| *len > 0)
+            .map(|((source, len), destination)| format!("{} {} {}", destination, source, len))
+            .collect();
+        ranges.shuffle(rng);
+        lines.extend(ranges);
+        blocks.push(lines.join("\n"));
+    }
+    blocks.join("\n\n")
+}
+
+/// At most four winnable races, so that the kerned race still fits in u64.
+pub fn race_sheet(rng: &mut StdRng, size: usize) -> String {
+    let num_races = size.min(4);
+    loop {
+        let times: Vec<u64> = (0..num_races).map(|_| rng.gen_range(7..100)).collect();
+        let distances: Vec<u64> = times.iter().map(|t| rng.gen_range(1..t * t / 4)).collect();
+
+        let kerned = |values: &[u64]| -> u64 {
+            values
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<String>()
+                .parse()
+                .unwrap()
+        };
+        let (time, distance) = (kerned(&times), kerned(&distances));
+        if distance >= time * time / 4 {
+            continue;
+        }
+
+        let format = |values: &[u64]| {
+            values
+                .iter()
+                .map(|v| format!("{:>6}", v))
+                .collect::<String>()
+        };
+        return format!(
+            "Time:    {}\nDistance:{}",
+            format(&times),
+            format(&distances)
+        );
+    }
+}
+
+pub fn camel_bids(rng: &mut StdRng, size: usize) -> String {
+    const CARDS: &[u8] = b"23456789TJQKA";
+    let mut hands: HashSet<String> = HashSet::new();
+    let mut lines = Vec::with_capacity(size);
+    while lines.len() < size {
+        let hand: String = (0..5)
+            .map(|_| *CARDS.choose(rng).unwrap() as char)
+            .collect();
+        if hands.insert(hand.clone()) {
+            lines.push(format!("{} {}", hand, rng.gen_range(1..=1000)));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Instructions, then a network where each `A` node leads to a loop going
+/// back to its `Z` node after as many steps as it took to reach it the
+/// first time. `AAA` leads to `ZZZ`.
+pub fn desert_network(rng: &mut StdRng, size: usize) -> String {
+    let instructions: String = (0..size)
+        .map(|_| if rng.gen_bool(0.5) { 'L' } else { 'R' })
+        .collect();
+
+    let mut names: HashSet<String> = HashSet::new();
+    let mut new_name = |rng: &mut StdRng, last: Option<char>| loop {
+        let mut name: String = (0..3).map(|_| rng.gen_range('B'..='Y')).collect();
+        if let Some(c) = last {
+            name.replace_range(2..3, &c.to_string());
+        }
+        if name != "AAA" && name != "ZZZ" && names.insert(name.clone()) {
+            return name;
+        }
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    let num_ghosts = 1 + size / 10;
+    for ghost in 0..num_ghosts.min(6) {
+        let length = rng.gen_range(1..=size + 1);
+        let mut path = Vec::with_capacity(length + 1);
+        path.push(match ghost {
+            0 => "AAA".to_string(),
+            _ => new_name(rng, Some('A')),
+        });
+        for _ in 1..length {
+            path.push(new_name(rng, None));
+        }
+        path.push(match ghost {
+            0 => "ZZZ".to_string(),
+            _ => new_name(rng, Some('Z')),
+        });
+        for step in 0..path.len() {
+            let next = if step == length {
+                &path[1]
+            } else {
+                &path[step + 1]
+            };
+            lines.push(format!("{} = ({}, {})", path[step], next, next));
+        }
+    }
+    lines.shuffle(rng);
+
+    format!("{}\n\n{}", instructions, lines.join("\n"))
+}
+
+/// Lines of 21 values of polynomials of degree up to 4.
+pub fn oasis_report(rng: &mut StdRng, size: usize) -> String {
+    let mut lines = Vec::with_capacity(size);
+    for _ in 0..size {
+        let coefficients: Vec<i64> = (0..rng.gen_range(1..=5))
+            .map(|_| rng.gen_range(-5..=5))
+            .collect();
+        let values: Vec<String> = (0..21i64)
+            .map(|x| {
+                coefficients
+                    .iter()
+                    .rev()
+                    .fold(0, |value, c| value * x + c)
+                    .to_string()
+            })
+            .collect();
+        lines.push(values.join(" "));
+    }
+    lines.join("\n")
+}
+
+/// Returns the moves of a closed loop followed clockwise, whose top and
+/// bottom borders are skylines of about `size` columns.
+fn clockwise_loop(rng: &mut StdRng, size: usize) -> Vec<(Direction, u32)> {
+    let mut moves: Vec<(Direction, u32)> = Vec::new();
+    let mut push = |direction: Direction, steps: u32| {
+        if steps == 0 {
+            return;
+        }
+        match moves.last_mut() {
+            Some((last, n)) if *last == direction => *n += steps,
+            _ => moves.push((direction, steps)),
+        }
+    };
+
+    let max = (size as u32 / 2).max(1);
+    let mut blocks: Vec<(u32, u32, u32)> = Vec::new();
+    let mut width = 0;
+    while width < size as u32 {
+        let block = (
+            rng.gen_range(1..=max),
+            rng.gen_range(1..=max),
+            rng.gen_range(0..=max),
+        );
+        width += block.0;
+        blocks.push(block);
+    }
+
+    let mut height = 0;
+    for (width, h, _) in &blocks {
+        if *h > height {
+            push(Direction::North, h - height);
+        } else {
+            push(Direction::South, height - h);
+        }
+        push(Direction::East, *width);
+        height = *h;
+    }
+    push(Direction::South, height);
+
+    let mut depth = 0;
+    for (width, _, d) in blocks.iter().rev() {
+        if *d > depth {
+            push(Direction::South, d - depth);
+        } else {
+            push(Direction::North, depth - d);
+        }
+        push(Direction::West, *width);
+        depth = *d;
+    }
+    push(Direction::North, depth);
+
+    moves
+}
+
+fn pipe(from: Direction, to: Direction) -> char {
+    let connects = |d: Direction| d == from || d == to;
+    match (
+        connects(Direction::North),
+        connects(Direction::East),
+        connects(Direction::South),
+    ) {
+        (true, false, true) => '|',
+        (false, true, false) => '-',
+        (true, true, false) => 'L',
+        (true, false, false) => 'J',
+        (false, false, true) => '7',
+        _ => 'F',
+    }
+}
+
+fn pipe_connects(c: char, direction: Direction) -> bool {
+    let directions: &[Direction] = match c {
+        '|' => &[Direction::North, Direction::South],
+        '-' => &[Direction::East, Direction::West],
+        'L' => &[Direction::North, Direction::East],
+        'J' => &[Direction::North, Direction::West],
+        '7' => &[Direction::South, Direction::West],
+        'F' => &[Direction::South, Direction::East],
+        _ => &[],
+    };
+    directions.contains(&direction)
+}
+
+/// A loop of pipes with its start `S`, surrounded by unconnected pipes.
+pub fn pipe_loop(rng: &mut StdRng, size: usize) -> String {
+    let moves = clockwise_loop(rng, size.max(2) - 1);
+
+    let mut cells: Vec<(Point, char)> = Vec::new();
+    let mut position = Point::new(0, 0);
+    let mut previous = moves.last().unwrap().0;
+    for (direction, steps) in &moves {
+        for _ in 0..*steps {
+            cells.push((position, pipe(previous.opposite(), *direction)));
+            position = position.to(*direction);
+            previous = *direction;
+        }
+    }
+
+    let min_row = cells.iter().map(|(p, _)| p.row()).min().unwrap();
+    let min_col = cells.iter().map(|(p, _)| p.col()).min().unwrap();
+    let num_rows = (cells.iter().map(|(p, _)| p.row()).max().unwrap() - min_row + 3) as usize;
+    let num_cols = (cells.iter().map(|(p, _)| p.col()).max().unwrap() - min_col + 3) as usize;
+
+    let mut rows: Vec<Vec<char>> = (0..num_rows)
+        .map(|_| {
+            (0..num_cols)
+                .map(|_| match rng.gen_bool(0.5) {
+                    true => '.',
+                    false => *b"|-LJ7F".choose(rng).unwrap() as char,
+                })
+                .collect()
+        })
+        .collect();
+    let mut on_loop: HashSet<(usize, usize)> = HashSet::new();
+    for (p, c) in &cells {
+        let (i, j) = (
+            (p.row() - min_row + 1) as usize,
+            (p.col() - min_col + 1) as usize,
+        );
+        rows[i][j] = *c;
+        on_loop.insert((i, j));
+    }
+
+    // clear the neighbours of the start, and pick a start with exactly two
+    // neighbours connecting to it
+    cells.shuffle(rng);
+    for (p, _) in &cells {
+        let (i, j) = (
+            (p.row() - min_row + 1) as usize,
+            (p.col() - min_col + 1) as usize,
+        );
+        let neighbours: Vec<(usize, usize, Direction)> = Direction::CARDINAL
+            .iter()
+            .map(|d| {
+                let (di, dj) = d.delta();
+                ((i as i64 + di) as usize, (j as i64 + dj) as usize, *d)
+            })
+            .collect();
+        let num_connected = neighbours
+            .iter()
+            .filter(|(ni, nj, d)| {
+                on_loop.contains(&(*ni, *nj)) && pipe_connects(rows[*ni][*nj], d.opposite())
+            })
+            .count();
+        if num_connected == 2 {
+            for (ni, nj, _) in neighbours {
+                if !on_loop.contains(&(ni, nj)) {
+                    rows[ni][nj] = '.';
+                }
+            }
+            rows[i][j] = 'S';
+            break;
+        }
+    }
+
+    rows.iter()
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn space_map(rng: &mut StdRng, size: usize) -> String {
+    grid(rng, size, |rng| if rng.gen_bool(0.05) { '#' } else { '.' })
+}
+
+/// Records built from random rows of springs, with some states unknown.
+pub fn condition_records(rng: &mut StdRng, size: usize) -> String {
+    let mut lines = Vec::with_capacity(size);
+    for _ in 0..size {
+        let len = rng.gen_range(3..=20);
+        let mut springs: Vec<char> = (0..len)
+            .map(|_| if rng.gen_bool(0.5) { '#' } else { '.' })
+            .collect();
+        let broken = rng.gen_range(0..len);
+        springs[broken] = '#';
+
+        let groups: Vec<String> = springs
+            .iter()
+            .collect::<String>()
+            .split('.')
+            .filter(|group| !group.is_empty())
+            .map(|group| group.len().to_string())
+            .collect();
+        for spring in springs.iter_mut() {
+            if rng.gen_bool(0.4) {
+                *spring = '?';
+            }
+        }
+        lines.push(format!(
+            "{} {}",
+            springs.iter().collect::<String>(),
+            groups.join(",")
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Patterns with a reflection line, separated by empty lines.
+pub fn mirror_notes(rng: &mut StdRng, size: usize) -> String {
+    let mut patterns = Vec::with_capacity(size);
+    for _ in 0..size {
+        let (num_rows, num_cols) = (rng.gen_range(5..=15), rng.gen_range(5..=15));
+        let vertical = rng.gen_bool(0.5);
+        let (len, width) = if vertical {
+            (num_cols, num_rows)
+        } else {
+            (num_rows, num_cols)
+        };
+
+        let mirror = rng.gen_range(1..len);
+        let mut lines: Vec<Vec<char>> = Vec::with_capacity(len);
+        for i in 0..len {
+            if i >= mirror && 2 * mirror > i {
+                let reflected = lines[2 * mirror - i - 1].clone();
+                lines.push(reflected);
+            } else {
+                lines.push(
+                    (0..width)
+                        .map(|_| if rng.gen_bool(0.5) { '#' } else { '.' })
+                        .collect(),
+                );
+            }
+        }
+
+        let rows: Vec<String> = if vertical {
+            (0..num_rows)
+                .map(|i| lines.iter().map(|line| line[i]).collect())
+                .collect()
+        } else {
+            lines.iter().map(|line| line.iter().collect()).collect()
+        };
+        patterns.push(rows.join("\n"));
+    }
+    patterns.join("\n\n")
+}
+
+pub fn dish(rng: &mut StdRng, size: usize) -> String {
+    grid(rng, size, |rng| match rng.gen_range(0..10) {
+        0..=2 => 'O',
+        3 => '#',
+        _ => '.',
+    })
+}
+
+/// Comma separated steps, on a small set of labels so that they interact.
+pub fn init_sequence(rng: &mut StdRng, size: usize) -> String {
+    let labels: Vec<String> = (0..size / 4 + 1)
+        .map(|_| {
+            let len = rng.gen_range(2..=6);
+            lowercase(rng, len)
+        })
+        .collect();
+    (0..size)
+        .map(|_| {
+            let label = labels.choose(rng).unwrap();
+            if rng.gen_bool(0.3) {
+                format!("{}-", label)
+            } else {
+                format!("{}={}", label, rng.gen_range(1..=9))
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+pub fn contraption(rng: &mut StdRng, size: usize) -> String {
+    grid(rng, size, |rng| match rng.gen_bool(0.15) {
+        true => *b"\\/-|".choose(rng).unwrap() as char,
+        false => '.',
+    })
+}
+
+pub fn loss_map(rng: &mut StdRng, size: usize) -> String {
+    grid(rng, size, |rng| rng.gen_range('1'..='9'))
+}
+
+/// A clockwise loop, with the colours encoding a scaled-up copy of it.
+pub fn dig_plan(rng: &mut StdRng, size: usize) -> String {
+    let moves = clockwise_loop(rng, size);
+    let max_steps = moves.iter().map(|(_, steps)| *steps).max().unwrap();
+    let scale = rng.gen_range(1..=0xfffff / max_steps);
+    moves
+        .iter()
+        .map(|(direction, steps)| {
+            let (letter, digit) = match direction {
+                Direction::East => ('R', 0),
+                Direction::South => ('D', 1),
+                Direction::West => ('L', 2),
+                _ => ('U', 3),
+            };
+            format!("{} {} (#{:05x}{})", letter, steps, steps * scale, digit)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Bricks in a small area, each on its own layers so that none overlap.
+pub fn brick_snapshot(rng: &mut StdRng, size: usize) -> String {
+    let side = ((size as f64).sqrt() as u32).max(3);
+    let mut z = 1;
+    let mut lines = Vec::with_capacity(size);
+    for _ in 0..size {
+        let len = rng.gen_range(0..side.min(4));
+        let (mut x, mut y) = (rng.gen_range(0..side), rng.gen_range(0..side));
+        let (mut dx, mut dy, mut dz) = (0, 0, 0);
+        match rng.gen_range(0..3) {
+            0 => {
+                x = x.min(side - 1 - len);
+                dx = len;
+            }
+            1 => {
+                y = y.min(side - 1 - len);
+                dy = len;
+            }
+            _ => dz = len,
+        }
+        lines.push(format!(
+            "{},{},{}~{},{},{}",
+            x,
+            y,
+            z,
+            x + dx,
+            y + dy,
+            z + dz
+        ));
+        z += dz + rng.gen_range(1..=3);
+    }
+    lines.shuffle(rng);
+    lines.join("\n")
+}
+
+/// Two well connected groups of components, wired together by three wires.
+pub fn wiring_diagram(rng: &mut StdRng, size: usize) -> String {
+    let num_components = 2 * (size / 2).max(6);
+    let mut names: Vec<String> = Vec::with_capacity(num_components);
+    while names.len() < num_components {
+        let name = lowercase(rng, 3);
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    let mut wires: HashSet<(usize, usize)> = HashSet::new();
+    let mut connect = |a: usize, b: usize| {
+        if a != b {
+            wires.insert((a.min(b), a.max(b)));
+        }
+    };
+    let half = num_components / 2;
+    for group in [0..half, half..num_components] {
+        let members: Vec<usize> = group.collect();
+        for (i, a) in members.iter().enumerate() {
+            connect(*a, members[(i + 1) % members.len()]);
+            for _ in 0..3 {
+                connect(*a, *members.choose(rng).unwrap());
+            }
+        }
+    }
+    let mut bridges = 0;
+    while bridges < 3 {
+        let (a, b) = (rng.gen_range(0..half), rng.gen_range(half..num_components));
+        if wires.insert((a, b)) {
+            bridges += 1;
+        }
+    }
+
+    let mut diagram: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (a, b) in &wires {
+        let (from, to) = if rng.gen_bool(0.5) { (a, b) } else { (b, a) };
+        diagram.entry(&names[*from]).or_default().push(&names[*to]);
+    }
+    let mut lines: Vec<String> = diagram
+        .iter()
+        .map(|(from, to)| format!("{}: {}", from, to.join(" ")))
+        .collect();
+    lines.shuffle(rng);
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution;
+
+    #[test]
+    fn test_generated_inputs_parse() {
+        for day in solution::days() {
+            for seed in 0..10 {
+                for size in [1, 2, 3, 5, 8, 13, 20] {
+                    let input = generate(day.number(), seed, size).unwrap();
+                    if let Err(e) = day.parse(&input) {
+                        panic!(
+                            "day {}, seed {}, size {}: {}\n{}",
+                            day.number(),
+                            seed,
+                            size,
+                            e,
+                            input
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_is_deterministic() {
+        assert_eq!(generate(5, 42, 10), generate(5, 42, 10));
+        assert_ne!(generate(5, 42, 10), generate(5, 43, 10));
+        assert_eq!(generate(19, 42, 10), None);
+    }
+
+    #[test]
+    fn test_generated_inputs_solve() {
+        for number in [2, 3, 4, 5, 6, 7, 8, 9, 10, 14, 15, 16, 18, 22, 25] {
+            let day = solution::day(number).unwrap();
+            for seed in 0..5 {
+                let solution = day.parse(&generate(number, seed, 12).unwrap()).unwrap();
+                solution.part1();
+                solution.part2();
+            }
+        }
+    }
+
+    #[test]
+    fn test_scratchcards_stay_solvable() {
+        let day = solution::day(4).unwrap();
+        for seed in 0..5 {
+            let solution = day.parse(&generate(4, seed, 1000).unwrap()).unwrap();
+            let total: u64 = solution.part2().unwrap().parse().unwrap();
+            assert!(total <= MAX_SCRATCHCARDS);
+        }
+    }
+
+    #[test]
+    fn test_pipe_loop() {
+        let input = generate(10, 7, 8).unwrap();
+        assert_eq!(input.matches('S').count(), 1);
+    }
+}
@@ -1,12 +1,40 @@
+use crate::error::ParseError;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
 #[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
 pub enum Direction {
     North,
+    NorthEast,
     East,
+    SouthEast,
     South,
+    SouthWest,
     West,
+    NorthWest,
 }
 
 impl Direction {
+    /// The four directions along rows and columns, clockwise from north.
+    pub const CARDINAL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// The eight directions, clockwise from north.
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
     pub fn is_east_west(&self) -> bool {
         self == &Direction::East || self == &Direction::West
     }
@@ -14,9 +42,98 @@ impl Direction {
     pub fn is_south_north(&self) -> bool {
         self == &Direction::South || self == &Direction::North
     }
+
+    pub fn is_diagonal(&self) -> bool {
+        !self.is_east_west() && !self.is_south_north()
+    }
+
+    /// Turns by a quarter, counterclockwise.
+    pub fn turn_left(&self) -> Direction {
+        self.rotate(6)
+    }
+
+    /// Turns by a quarter, clockwise.
+    pub fn turn_right(&self) -> Direction {
+        self.rotate(2)
+    }
+
+    pub fn opposite(&self) -> Direction {
+        self.rotate(4)
+    }
+
+    /// Rotates clockwise by `eighths` eighths of a turn.
+    fn rotate(&self, eighths: usize) -> Direction {
+        let index = Direction::ALL.iter().position(|d| d == self).unwrap();
+        Direction::ALL[(index + eighths) % 8]
+    }
+
+    /// Returns the (row, column) increment of one step in this direction,
+    /// rows growing southward and columns eastward.
+    pub fn delta(&self) -> (i64, i64) {
+        match self {
+            Direction::North => (-1, 0),
+            Direction::NorthEast => (-1, 1),
+            Direction::East => (0, 1),
+            Direction::SouthEast => (1, 1),
+            Direction::South => (1, 0),
+            Direction::SouthWest => (1, -1),
+            Direction::West => (0, -1),
+            Direction::NorthWest => (-1, -1),
+        }
+    }
+}
+
+/// Point of an unbounded plane with signed coordinates.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub struct Point {
+    row: i64,
+    column: i64,
+}
+
+impl Point {
+    pub fn new(row: i64, column: i64) -> Point {
+        Point { row, column }
+    }
+
+    pub fn row(&self) -> i64 {
+        self.row
+    }
+
+    pub fn col(&self) -> i64 {
+        self.column
+    }
+
+    pub fn to(&self, direction: Direction) -> Point {
+        self.moved(direction, 1)
+    }
+
+    pub fn moved(&self, direction: Direction, steps: i64) -> Point {
+        let (row, column) = direction.delta();
+        Point::new(self.row + row * steps, self.column + column * steps)
+    }
+
+    pub fn manhattan_distance(&self, other: &Point) -> u64 {
+        self.row.abs_diff(other.row) + self.column.abs_diff(other.column)
+    }
+
+    /// Returns the position this point falls on when a grid of the given
+    /// size is repeated infinitely in every direction.
+    pub fn wrap(&self, size: (usize, usize)) -> Position {
+        Position::new(
+            self.row.rem_euclid(size.0 as i64) as usize,
+            self.column.rem_euclid(size.1 as i64) as usize,
+            size,
+        )
+    }
 }
 
-#[derive(PartialEq, Clone, Copy)]
+impl From<Position> for Point {
+    fn from(position: Position) -> Point {
+        Point::new(position.row as i64, position.column as i64)
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub struct Position {
     row: usize,
     column: usize,
@@ -29,39 +146,347 @@ impl Position {
     }
 
     pub fn to(&self, direction: Direction) -> Option<Position> {
-        match direction {
-            Direction::North => {
-                if self.row == 0 {
-                    return None;
-                }
-                return Some(Position::new(self.row - 1, self.column, self.size));
+        let (row, column) = direction.delta();
+        let row = self.row.checked_add_signed(row as isize)?;
+        let column = self.column.checked_add_signed(column as isize)?;
+        if row >= self.size.0 || column >= self.size.1 {
+            return None;
+        }
+        Some(Position::new(row, column, self.size))
+    }
+
+    /// Moves one step, leaving the grid on one side to enter it on the
+    /// other side.
+    pub fn wrapping_to(&self, direction: Direction) -> Position {
+        Point::from(*self).to(direction).wrap(self.size)
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn col(&self) -> usize {
+        self.column
+    }
+}
+
+/// Rectangular grid of cells, stored row after row.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Grid<T> {
+    num_rows: usize,
+    num_cols: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(num_rows: usize, num_cols: usize, value: T) -> Grid<T> {
+        Grid {
+            num_rows,
+            num_cols,
+            cells: vec![value; num_rows * num_cols],
+        }
+    }
+
+    pub fn transpose(&self) -> Grid<T> {
+        Grid::from_fn(self.num_cols, self.num_rows, |i, j| self[(j, i)].clone())
+    }
+
+    pub fn rotate_clockwise(&self) -> Grid<T> {
+        Grid::from_fn(self.num_cols, self.num_rows, |i, j| {
+            self[(self.num_rows - 1 - j, i)].clone()
+        })
+    }
+
+    pub fn rotate_counterclockwise(&self) -> Grid<T> {
+        Grid::from_fn(self.num_cols, self.num_rows, |i, j| {
+            self[(j, self.num_cols - 1 - i)].clone()
+        })
+    }
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid whose cells are computed from their row and column.
+    pub fn from_fn<F>(num_rows: usize, num_cols: usize, mut f: F) -> Grid<T>
+    where
+        F: FnMut(usize, usize) -> T,
+    {
+        let mut cells = Vec::with_capacity(num_rows * num_cols);
+        for i in 0..num_rows {
+            for j in 0..num_cols {
+                cells.push(f(i, j));
             }
-            Direction::East => {
-                if self.column == self.size.1 - 1 {
-                    return None;
+        }
+        Grid {
+            num_rows,
+            num_cols,
+            cells,
+        }
+    }
+
+    /// Parses a rectangular grid of characters, one line per row.
+    pub fn try_parse_with<F>(input: &str, parse_cell: F) -> Result<Grid<T>, ParseError>
+    where
+        F: Fn(char) -> Result<T, ParseError>,
+    {
+        let mut grid = Grid {
+            num_rows: 0,
+            num_cols: 0,
+            cells: Vec::with_capacity(input.len()),
+        };
+        for line in input.lines() {
+            let mut num_cols = 0usize;
+            for (i, c) in line.char_indices() {
+                let token = &line[i..i + c.len_utf8()];
+                if grid.num_rows > 0 && num_cols == grid.num_cols {
+                    return Err(ParseError::at(input, token, "end of line"));
                 }
-                return Some(Position::new(self.row, self.column + 1, self.size));
+                grid.cells
+                    .push(parse_cell(c).map_err(|e| e.within(input, token))?);
+                num_cols += 1;
             }
-            Direction::South => {
-                if self.row == self.size.0 - 1 {
-                    return None;
-                }
-                return Some(Position::new(self.row + 1, self.column, self.size));
+            if grid.num_rows == 0 {
+                grid.num_cols = num_cols;
+            } else if num_cols < grid.num_cols {
+                return Err(ParseError::at(input, &line[line.len()..], "grid cell"));
             }
-            Direction::West => {
-                if self.column == 0 {
-                    return None;
-                }
-                return Some(Position::new(self.row, self.column - 1, self.size));
+            grid.num_rows += 1;
+        }
+
+        Ok(grid)
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.num_cols
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.num_rows, self.num_cols)
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Option<&T> {
+        if row >= self.num_rows || column >= self.num_cols {
+            return None;
+        }
+        Some(&self.cells[row * self.num_cols + column])
+    }
+
+    pub fn get_mut(&mut self, row: usize, column: usize) -> Option<&mut T> {
+        if row >= self.num_rows || column >= self.num_cols {
+            return None;
+        }
+        Some(&mut self.cells[row * self.num_cols + column])
+    }
+
+    pub fn position(&self, row: usize, column: usize) -> Position {
+        Position::new(row, column, self.size())
+    }
+
+    /// Returns the position of `point`, if it is inside the grid.
+    pub fn position_of(&self, point: Point) -> Option<Position> {
+        if point.row() < 0
+            || point.col() < 0
+            || point.row() as usize >= self.num_rows
+            || point.col() as usize >= self.num_cols
+        {
+            return None;
+        }
+        Some(self.position(point.row() as usize, point.col() as usize))
+    }
+
+    /// Iterates over the cells and their positions, row after row.
+    pub fn iter(&self) -> impl Iterator<Item = (Position, &T)> {
+        let size = self.size();
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(index, cell)| (Position::new(index / size.1, index % size.1, size), cell))
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.num_cols..(row + 1) * self.num_cols]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.num_rows).map(|i| self.row(i))
+    }
+
+    pub fn column(&self, column: usize) -> impl Iterator<Item = &T> {
+        assert!(column < self.num_cols, "column out of the grid");
+        (0..self.num_rows).map(move |i| &self.cells[i * self.num_cols + column])
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.num_cols).map(|j| self.column(j))
+    }
+
+    /// Iterates over the positions next to `position`, in the four
+    /// directions, that are inside the grid.
+    pub fn neighbours(&self, position: Position) -> impl Iterator<Item = (Direction, Position)> {
+        Direction::CARDINAL
+            .into_iter()
+            .filter_map(move |direction| position.to(direction).map(|next| (direction, next)))
+    }
+
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid {
+            num_rows: self.num_rows,
+            num_cols: self.num_cols,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Renders the grid with one character per cell and one line per row.
+    pub fn render<F: Fn(&T) -> char>(&self, render_cell: F) -> String {
+        let mut s = String::with_capacity(self.num_rows * (self.num_cols + 1));
+        for (i, row) in self.rows().enumerate() {
+            if i > 0 {
+                s.push('\n');
             }
-        };
+            s.extend(row.iter().map(&render_cell));
+        }
+        s
     }
+}
 
-    pub fn row(&self) -> usize {
-        self.row
+impl<T: TryFrom<char, Error = ParseError>> Grid<T> {
+    pub fn try_parse(input: &str) -> Result<Grid<T>, ParseError> {
+        Grid::try_parse_with(input, T::try_from)
     }
+}
 
-    pub fn col(&self) -> usize {
-        self.column
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (row, column): (usize, usize)) -> &T {
+        assert!(column < self.num_cols, "column out of the grid");
+        &self.cells[row * self.num_cols + column]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut T {
+        assert!(column < self.num_cols, "column out of the grid");
+        &mut self.cells[row * self.num_cols + column]
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: Position) -> &T {
+        &self[(position.row(), position.col())]
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, position: Position) -> &mut T {
+        &mut self[(position.row(), position.col())]
+    }
+}
+
+impl<T: Copy + Into<char>> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(|cell| (*cell).into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Found;
+
+    fn parse(input: &str) -> Grid<char> {
+        Grid::try_parse_with(input, Ok).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_render() {
+        let g = parse("abc\ndef");
+        assert_eq!(g.size(), (2, 3));
+        assert_eq!(g[(1, 0)], 'd');
+        assert_eq!(g.get(2, 0), None);
+        assert_eq!(format!("{}", g), "abc\ndef");
+        assert_eq!(parse("").size(), (0, 0));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let e = Grid::try_parse_with("abc\nde", Ok).err().unwrap();
+        assert_eq!((e.line(), e.column()), (2, 3));
+        assert_eq!(e.found(), &Found::EndOfInput);
+
+        let e = Grid::try_parse_with("abc\ndefg", Ok).err().unwrap();
+        assert_eq!((e.line(), e.column()), (2, 4));
+
+        let e = Grid::try_parse_with("ab\nc?", |c| match c {
+            '?' => Err(ParseError::new(1, 1, "letter", Found::Token(c.to_string()))),
+            _ => Ok(c),
+        })
+        .err()
+        .unwrap();
+        assert_eq!((e.line(), e.column()), (2, 2));
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let g = parse("abc\ndef");
+        let rows: Vec<String> = g.rows().map(|row| row.iter().collect()).collect();
+        assert_eq!(rows, vec!["abc", "def"]);
+        let columns: Vec<String> = g.columns().map(|column| column.collect()).collect();
+        assert_eq!(columns, vec!["ad", "be", "cf"]);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let g = parse("abc\ndef");
+        let neighbours: Vec<char> = g.neighbours(g.position(0, 1)).map(|(_, p)| g[p]).collect();
+        assert_eq!(neighbours, vec!['c', 'e', 'a']);
+    }
+
+    #[test]
+    fn test_turn() {
+        assert_eq!(Direction::North.turn_left(), Direction::West);
+        assert_eq!(Direction::West.turn_right(), Direction::North);
+        assert_eq!(Direction::SouthEast.turn_right(), Direction::SouthWest);
+        assert_eq!(Direction::NorthEast.opposite(), Direction::SouthWest);
+        for d in Direction::ALL {
+            let (row, column) = d.delta();
+            assert_eq!(d.opposite().delta(), (-row, -column));
+            assert_eq!(d.turn_left().turn_left(), d.opposite());
+        }
+    }
+
+    #[test]
+    fn test_point() {
+        let p = Point::new(-2, 3);
+        assert_eq!(p.to(Direction::NorthWest), Point::new(-3, 2));
+        assert_eq!(p.moved(Direction::South, 5), Point::new(3, 3));
+        assert_eq!(p.manhattan_distance(&Point::new(1, -1)), 7);
+        assert_eq!(p.wrap((2, 2)), Position::new(0, 1, (2, 2)));
+    }
+
+    #[test]
+    fn test_position_to() {
+        let g = parse("abc\ndef");
+        let p = g.position(0, 2);
+        assert_eq!(p.to(Direction::SouthWest), Some(g.position(1, 1)));
+        assert_eq!(p.to(Direction::East), None);
+        assert_eq!(p.wrapping_to(Direction::East), g.position(0, 0));
+        assert_eq!(p.wrapping_to(Direction::NorthEast), g.position(1, 0));
+        assert_eq!(g.position_of(Point::new(1, 2)), Some(g.position(1, 2)));
+        assert_eq!(g.position_of(Point::new(-1, 2)), None);
+    }
+
+    #[test]
+    fn test_transpose_and_rotate() {
+        let g = parse("abc\ndef");
+        assert_eq!(g.transpose().render(|c| *c), "ad\nbe\ncf");
+        assert_eq!(g.rotate_clockwise().render(|c| *c), "da\neb\nfc");
+        assert_eq!(g.rotate_counterclockwise().render(|c| *c), "cf\nbe\nad");
+        assert_eq!(g.rotate_clockwise().rotate_counterclockwise(), g);
     }
 }
@@ -1,33 +1,60 @@
+use crate::error::{parse_token, ParseError};
+
 pub fn sum_hash(input: &str) -> u64 {
-    input.split(|c| c == ',').map(|s| hash(s) as u64).sum()
+    sum_hash_of(input.split(|c| c == ','))
+}
+
+/// Sums the hashes of steps, as written in the sequence.
+pub fn sum_hash_of<'a>(steps: impl IntoIterator<Item = &'a str>) -> u64 {
+    steps.into_iter().map(|s| hash(s) as u64).sum()
 }
 
 pub fn focusing_power(input: &str) -> u64 {
+    let steps = try_read_steps(input).unwrap_or_else(|e| panic!("{}", e));
+    focusing_power_of(&steps)
+}
+
+pub fn focusing_power_of(steps: &[Step]) -> u64 {
     let mut boxes: Vec<LensBox> = Vec::with_capacity(256);
     for _ in 0..256 {
         boxes.push(LensBox::new());
     }
 
-    input.split(|c| c == ',').for_each(|s| {
-        if let Some(index) = s.find('=') {
-            let label = String::from(&s[..index]);
-            let focal: u64 = s[index + 1..].parse().unwrap();
-            boxes[hash(&s[..index]) as usize].add(label, focal);
-            return;
-        }
-        if let Some(index) = s.find('-') {
-            boxes[hash(&s[..index]) as usize].remove(&s[..index]);
-            return;
+    for step in steps {
+        match step {
+            Step::Insert(label, focal) => boxes[hash(label) as usize].add(label.clone(), *focal),
+            Step::Remove(label) => boxes[hash(label) as usize].remove(label),
         }
-    });
+    }
 
     let mut pwr = 0u64;
-    for i in 0..256 {
-        pwr += (i as u64 + 1) * boxes[i].focusing_power();
+    for (i, lens_box) in boxes.iter().enumerate() {
+        pwr += (i as u64 + 1) * lens_box.focusing_power();
     }
     pwr
 }
 
+/// A step of the initialization sequence.
+pub enum Step {
+    Insert(String, u64),
+    Remove(String),
+}
+
+pub fn try_read_steps(input: &str) -> Result<Vec<Step>, ParseError> {
+    let mut steps: Vec<Step> = Vec::new();
+    for s in input.trim_end().split(',') {
+        if let Some(index) = s.find('=') {
+            let focal: u64 = parse_token(input, &s[index + 1..], "focal length")?;
+            steps.push(Step::Insert(s[..index].to_string(), focal));
+        } else if let Some(label) = s.strip_suffix('-') {
+            steps.push(Step::Remove(label.to_string()));
+        } else {
+            return Err(ParseError::at(input, &s[s.len()..], "'=' or '-'"));
+        }
+    }
+    Ok(steps)
+}
+
 fn hash(input: &str) -> u8 {
     let mut h = 0u16;
     for b in input.bytes() {
@@ -160,6 +187,15 @@ mod tests {
         assert_eq!(b.focusing_power(), 1);
     }
 
+    #[test]
+    fn test_read_steps_error() {
+        let e = try_read_steps("rn=1,cm-,qp=x,cm=2").err().unwrap();
+        assert_eq!((e.line(), e.column()), (1, 13));
+
+        let e = try_read_steps("rn=1,cm,qp=3").err().unwrap();
+        assert_eq!(e.expected(), "'=' or '-'");
+    }
+
     #[test]
     fn test_focusing_power() {
         assert_eq!(
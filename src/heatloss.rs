@@ -1,55 +1,46 @@
+use crate::error::{Found, ParseError};
+use crate::grid2d::{Direction, Grid, Point};
 use std::{collections::HashSet, fmt};
 
 pub struct LossMap {
-    content: Vec<Vec<u64>>,
-    num_rows: usize,
-    num_cols: usize,
+    content: Grid<u64>,
 }
 
 impl LossMap {
     pub fn parse(input: &str) -> LossMap {
-        let mut m = LossMap {
-            content: Vec::new(),
-            num_rows: 0,
-            num_cols: 0,
-        };
-
-        let mut is_first_line: bool = true;
-        for line in input.lines() {
-            if is_first_line {
-                let mut row: Vec<u64> = Vec::new();
-                for c in line.chars() {
-                    row.push(c.to_digit(10).unwrap() as u64);
-                }
-                m.num_cols = row.len();
-                m.content.push(row);
-                is_first_line = false;
-                continue;
-            }
+        LossMap::try_parse(input).unwrap_or_else(|e| panic!("{}", e))
+    }
 
-            let mut row: Vec<u64> = Vec::with_capacity(m.num_cols);
-            for c in line.chars() {
-                row.push(c.to_digit(10).unwrap() as u64);
-            }
-            m.content.push(row);
-        }
-        m.num_rows = m.content.len();
+    pub fn try_parse(input: &str) -> Result<LossMap, ParseError> {
+        let content = Grid::try_parse_with(input, |c| match c.to_digit(10) {
+            Some(d) => Ok(d as u64),
+            None => Err(ParseError::new(1, 1, "digit", Found::Token(c.to_string()))),
+        })?;
 
-        m
+        Ok(LossMap { content })
     }
 
     pub fn shortest_path(&self) -> u64 {
-        let mut visited: HashSet<(i32, i32)> = HashSet::new();
-        visited.insert((0, 0));
-        (self.content[0][1] + self.shortest_path_from(&mut visited, (0, 1), (0, 1), 1))
-            .min(self.content[1][0] + self.shortest_path_from(&mut visited, (1, 0), (1, 0), 1))
+        let mut visited: HashSet<Point> = HashSet::new();
+        let start = Point::new(0, 0);
+        visited.insert(start);
+        [Direction::East, Direction::South]
+            .into_iter()
+            .filter_map(|direction| {
+                let next = start.to(direction);
+                let loss = self.content[self.content.position_of(next)?];
+                let shortest_path = self.shortest_path_from(&mut visited, next, direction, 1);
+                Some(loss.saturating_add(shortest_path))
+            })
+            .min()
+            .unwrap_or(u64::MAX)
     }
 
     fn shortest_path_from(
         &self,
-        visited: &mut HashSet<(i32, i32)>,
-        pos: (i32, i32),
-        latest_incr: (i32, i32),
+        visited: &mut HashSet<Point>,
+        pos: Point,
+        latest_direction: Direction,
         dist_since_last_turn: u8,
     ) -> u64 {
         if visited.contains(&pos) {
@@ -57,7 +48,11 @@ impl LossMap {
         }
         visited.insert(pos);
 
-        if pos == (self.num_rows as i32 - 1, self.num_cols as i32 - 1) {
+        let end = Point::new(
+            self.content.num_rows() as i64 - 1,
+            self.content.num_cols() as i64 - 1,
+        );
+        if pos == end {
             visited.remove(&pos);
             return 0;
         }
@@ -65,43 +60,27 @@ impl LossMap {
         let mut dist = u64::MAX;
 
         if dist_since_last_turn < 3 {
-            let next = (pos.0 + latest_incr.0, pos.1 + latest_incr.1);
-            if next.0 >= 0
-                && (next.0 as usize) < self.num_rows
-                && next.1 >= 0
-                && (next.1 as usize) < self.num_cols
-            {
-                let shortest_path =
-                    self.shortest_path_from(visited, next, latest_incr, dist_since_last_turn + 1);
+            let next = pos.to(latest_direction);
+            if let Some(position) = self.content.position_of(next) {
+                let shortest_path = self.shortest_path_from(
+                    visited,
+                    next,
+                    latest_direction,
+                    dist_since_last_turn + 1,
+                );
                 if shortest_path != u64::MAX {
-                    dist = self.content[next.0 as usize][next.1 as usize] + shortest_path;
+                    dist = self.content[position] + shortest_path;
                 }
             }
         }
 
-        let mut incr = (latest_incr.1, latest_incr.0);
-        let mut next = (pos.0 + incr.0, pos.1 + incr.1);
-        if next.0 >= 0
-            && (next.0 as usize) < self.num_rows
-            && next.1 >= 0
-            && (next.1 as usize) < self.num_cols
-        {
-            let shortest_path = self.shortest_path_from(visited, next, incr, 1);
-            if shortest_path != u64::MAX {
-                dist = dist.min(self.content[next.0 as usize][next.1 as usize] + shortest_path);
-            }
-        }
-
-        incr = (-incr.0, -incr.1);
-        next = (pos.0 + incr.0, pos.1 + incr.1);
-        if next.0 >= 0
-            && (next.0 as usize) < self.num_rows
-            && next.1 >= 0
-            && (next.1 as usize) < self.num_cols
-        {
-            let shortest_path = self.shortest_path_from(visited, next, incr, 1);
-            if shortest_path != u64::MAX {
-                dist = dist.min(self.content[next.0 as usize][next.1 as usize] + shortest_path);
+        for direction in [latest_direction.turn_left(), latest_direction.turn_right()] {
+            let next = pos.to(direction);
+            if let Some(position) = self.content.position_of(next) {
+                let shortest_path = self.shortest_path_from(visited, next, direction, 1);
+                if shortest_path != u64::MAX {
+                    dist = dist.min(self.content[position] + shortest_path);
+                }
             }
         }
 
@@ -112,24 +91,15 @@ impl LossMap {
 
 impl fmt::Display for LossMap {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.num_rows == 0 {
-            return write!(f, "");
-        }
-
-        let mut s = String::with_capacity(self.num_rows * (self.num_cols + 1) - 1);
-        for i in 0..self.num_rows {
-            for j in 0..self.num_cols {
-                s.push(char::from_digit(self.content[i][j] as u32, 10).unwrap());
-            }
-            if i < self.num_rows - 1 {
-                s.push('\n');
-            }
-        }
-        write!(f, "{}", s)
+        write!(
+            f,
+            "{}",
+            self.content
+                .render(|loss| char::from_digit(*loss as u32, 10).unwrap())
+        )
     }
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -154,6 +124,13 @@ mod tests {
         assert_eq!(format!("{}", c), INPUT);
     }
 
+    #[test]
+    fn test_parse_map_error() {
+        let e = LossMap::try_parse("2413\n321").err().unwrap();
+        assert_eq!((e.line(), e.column()), (2, 4));
+        assert_eq!(e.found(), &Found::EndOfInput);
+    }
+
     // #[test]
     // fn test_shortest_path() {
     //     let m = LossMap::parse(&INPUT);
@@ -165,4 +142,4 @@ mod tests {
         let m = LossMap::parse("21111111\n22288222");
         assert_eq!(m.shortest_path(), 15);
     }
-}
\ No newline at end of file
+}
@@ -1,3 +1,7 @@
+use crate::error::{parse_token, Found, ParseError, ReadError};
+use crate::stream::parse_lines;
+use std::io::BufRead;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 enum State {
     Operational,
@@ -6,14 +10,19 @@ enum State {
 }
 
 impl TryFrom<char> for State {
-    type Error = &'static str;
+    type Error = ParseError;
 
     fn try_from(c: char) -> Result<Self, Self::Error> {
         match c {
             '.' => Ok(State::Operational),
             '#' => Ok(State::Damaged),
             '?' => Ok(State::Unknown),
-            _ => Err("invalid state character"),
+            _ => Err(ParseError::new(
+                1,
+                1,
+                "'.', '#' or '?'",
+                Found::Token(c.to_string()),
+            )),
         }
     }
 }
@@ -25,24 +34,31 @@ pub struct ConditionRecord {
 
 impl ConditionRecord {
     pub fn parse(input: &str) -> ConditionRecord {
+        ConditionRecord::try_parse(input).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_parse(input: &str) -> Result<ConditionRecord, ParseError> {
         let mut record = ConditionRecord {
             states: Vec::new(),
             group_sizes: Vec::new(),
         };
 
-        let states_end = input.find(|c: char| c.is_whitespace()).unwrap();
-        input[..states_end]
-            .chars()
-            .map(|c| State::try_from(c).unwrap())
-            .for_each(|s| record.states.push(s));
+        let states_end = input
+            .find(|c: char| c.is_whitespace())
+            .ok_or_else(|| ParseError::at_end(input, "group sizes"))?;
+        for (i, c) in input[..states_end].char_indices() {
+            let state =
+                State::try_from(c).map_err(|e| e.within(input, &input[i..i + c.len_utf8()]))?;
+            record.states.push(state);
+        }
 
-        let group_sizes_start = input.find(|c: char| c.is_digit(10)).unwrap();
-        input[group_sizes_start..]
-            .split(',')
-            .map(|s| s.parse::<usize>().unwrap())
-            .for_each(|n| record.group_sizes.push(n));
+        for str_size in input[states_end..].trim().split(',') {
+            record
+                .group_sizes
+                .push(parse_token(input, str_size, "group size")?);
+        }
 
-        record
+        Ok(record)
     }
 
     pub fn unfold(&self, n: usize) -> ConditionRecord {
@@ -143,19 +159,54 @@ struct PositionedGroup {
 }
 
 pub fn read_records(input: &str) -> Vec<ConditionRecord> {
-    let mut records: Vec<ConditionRecord> = Vec::new();
+    try_read_records(input).unwrap_or_else(|e| panic!("{}", e))
+}
 
-    for line in input.lines() {
-        records.push(ConditionRecord::parse(line));
-    }
+/// Lazily reads condition records, one per line.
+pub fn stream_records<R: BufRead>(
+    reader: R,
+) -> impl Iterator<Item = Result<ConditionRecord, ReadError>> {
+    parse_lines(reader, ConditionRecord::try_parse)
+}
 
-    records
+pub fn try_read_records(input: &str) -> Result<Vec<ConditionRecord>, ParseError> {
+    input
+        .lines()
+        .map(|line| ConditionRecord::try_parse(line).map_err(|e| e.within(input, line)))
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_read_records_errors() {
+        let e = try_read_records("???.### 1,1,3\n.??.x 1,1").err().unwrap();
+        assert_eq!((e.line(), e.column()), (2, 5));
+
+        let e = try_read_records("???.### 1,,3").err().unwrap();
+        assert_eq!((e.line(), e.column()), (1, 11));
+        assert_eq!(e.expected(), "group size");
+
+        let e = ConditionRecord::try_parse("???.###").err().unwrap();
+        assert_eq!(e.found(), &Found::EndOfInput);
+    }
+
+    #[test]
+    fn test_stream_records() {
+        let total: Result<u64, ReadError> =
+            stream_records("???.### 1,1,3\n.??..??...?##. 1,1,3\n".as_bytes())
+                .map(|r| r.map(|r| r.count_arrangements()))
+                .sum();
+        assert_eq!(total.unwrap(), 5);
+
+        match stream_records("???.### 1,1,3\n.??.x 1,1".as_bytes()).nth(1) {
+            Some(Err(ReadError::Parse(e))) => assert_eq!((e.line(), e.column()), (2, 5)),
+            _ => panic!("expected a parse error"),
+        }
+    }
+
     #[test]
     fn test_count_arrangements_1() {
         let record = ConditionRecord::parse("???.### 1,1,3");
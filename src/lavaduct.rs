@@ -1,8 +1,13 @@
+use crate::error::{parse_token, ParseError, ReadError};
 use crate::grid2d::Direction;
+use crate::grid2d::Grid;
+use crate::grid2d::Point;
 use crate::grid2d::Position;
+use crate::stream::parse_lines;
 use std::cmp::Ordering;
 use std::collections::HashSet;
 use std::fmt;
+use std::io::BufRead;
 
 pub struct Instruction {
     dir: Direction,
@@ -47,46 +52,38 @@ enum Lagoon {
 }
 
 pub struct DigMap {
-    content: Vec<Vec<Lagoon>>,
-    num_rows: usize,
-    num_cols: usize,
+    content: Grid<Lagoon>,
 }
 
 impl DigMap {
     pub fn volume(&self) -> u64 {
-        let mut count = 0u64;
-        for i in 0..self.num_rows {
-            for j in 0..self.num_cols {
-                let state = self.content[i][j];
-                if state == Lagoon::Border || state == Lagoon::Inside {
-                    count += 1;
-                }
-            }
-        }
-        count
+        self.content
+            .iter()
+            .filter(|(_, state)| **state != Lagoon::Outside)
+            .count() as u64
     }
 
     fn dig_border(&mut self, position: &Position) {
-        self.content[position.row()][position.col()] = Lagoon::Border;
+        self.content[*position] = Lagoon::Border;
     }
 
     fn dig_to_south(&mut self, position: &Position) {
-        for i in position.row()..self.num_rows {
-            let state = self.content[i][position.col()];
+        for i in position.row()..self.content.num_rows() {
+            let state = self.content[(i, position.col())];
             if state == Lagoon::Border || state == Lagoon::Inside {
                 break;
             }
-            self.content[i][position.col()] = Lagoon::Inside;
+            self.content[(i, position.col())] = Lagoon::Inside;
         }
     }
 
     fn undig_to_south(&mut self, position: &Position) {
-        for i in position.row()..self.num_rows {
-            let state = self.content[i][position.col()];
+        for i in position.row()..self.content.num_rows() {
+            let state = self.content[(i, position.col())];
             if state == Lagoon::Border || state == Lagoon::Outside {
                 break;
             }
-            self.content[i][position.col()] = Lagoon::Outside;
+            self.content[(i, position.col())] = Lagoon::Outside;
         }
     }
 
@@ -105,79 +102,97 @@ impl DigMap {
 
 impl fmt::Display for DigMap {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.num_rows == 0 {
-            return write!(f, "");
-        }
-
-        let mut s = String::with_capacity(self.num_rows * (self.num_cols + 1) - 1);
-        for i in 0..self.num_rows {
-            for j in 0..self.num_cols {
-                s.push(match self.content[i][j] {
-                    Lagoon::Border => '#',
-                    Lagoon::Inside => '#',
-                    Lagoon::Outside => '.',
-                });
-            }
-            if i < self.num_rows - 1 {
-                s.push('\n');
-            }
-        }
-        write!(f, "{}", s)
+        write!(
+            f,
+            "{}",
+            self.content.render(|state| match state {
+                Lagoon::Border => '#',
+                Lagoon::Inside => '#',
+                Lagoon::Outside => '.',
+            })
+        )
     }
 }
 
 pub fn read_dig_plan(input: &str) -> Vec<Instruction> {
-    let mut instructions: Vec<Instruction> = Vec::new();
+    try_read_dig_plan(input).unwrap_or_else(|e| panic!("{}", e))
+}
 
-    for line in input.lines() {
-        let mut parts = line.split_whitespace();
-        let dir = direction_from_str(parts.next().unwrap()).unwrap();
-        let num_steps: u32 = parts.next().unwrap().parse().unwrap();
-        instructions.push(Instruction { dir, num_steps });
-    }
+pub fn try_read_dig_plan(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    input
+        .lines()
+        .map(|line| try_parse_instruction(line).map_err(|e| e.within(input, line)))
+        .collect()
+}
+
+/// Lazily reads a dig plan, one instruction per line.
+pub fn stream_dig_plan<R: BufRead>(
+    reader: R,
+) -> impl Iterator<Item = Result<Instruction, ReadError>> {
+    parse_lines(reader, try_parse_instruction)
+}
 
-    instructions
+fn try_parse_instruction(line: &str) -> Result<Instruction, ParseError> {
+    let mut parts = line.split_whitespace();
+    let str_dir = parts
+        .next()
+        .ok_or_else(|| ParseError::at(line, line, "direction"))?;
+    let dir = direction_from_str(str_dir)
+        .ok_or_else(|| ParseError::at(line, str_dir, "'R', 'D', 'L' or 'U'"))?;
+    let str_steps = parts
+        .next()
+        .ok_or_else(|| ParseError::at_end(line, "number of steps"))?;
+    let num_steps: u32 = parse_token(line, str_steps, "number of steps")?;
+    Ok(Instruction { dir, num_steps })
 }
 
 pub fn read_dig_plan_correctly(input: &str) -> Vec<Instruction> {
-    let mut instructions: Vec<Instruction> = Vec::new();
-
-    for line in input.lines() {
-        let sharp_idx = line.find('#').unwrap();
-        let parenth_idx = line.find(')').unwrap();
-        instructions.push(Instruction {
-            dir: direction_from_str_digit(&line[parenth_idx - 1..parenth_idx]).unwrap(),
-            num_steps: u32::from_str_radix(&line[sharp_idx + 1..parenth_idx - 1], 16).unwrap(),
-        })
-    }
+    try_read_dig_plan_correctly(input).unwrap_or_else(|e| panic!("{}", e))
+}
 
-    instructions
+pub fn try_read_dig_plan_correctly(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    input
+        .lines()
+        .map(|line| try_parse_instruction_correctly(line).map_err(|e| e.within(input, line)))
+        .collect()
+}
+
+/// Lazily reads a dig plan from the colours, one instruction per line.
+pub fn stream_dig_plan_correctly<R: BufRead>(
+    reader: R,
+) -> impl Iterator<Item = Result<Instruction, ReadError>> {
+    parse_lines(reader, try_parse_instruction_correctly)
+}
+
+fn try_parse_instruction_correctly(line: &str) -> Result<Instruction, ParseError> {
+    let sharp_idx = line
+        .find("(#")
+        .ok_or_else(|| ParseError::at_end(line, "'(#'"))?
+        + 1;
+    let parenth_idx = line[sharp_idx..]
+        .find(')')
+        .ok_or_else(|| ParseError::at_end(line, "')'"))?
+        + sharp_idx;
+    let colour = &line[sharp_idx + 1..parenth_idx];
+    if colour.len() != 6 || !colour.is_char_boundary(5) {
+        return Err(ParseError::at(line, colour, "6 hexadecimal digits"));
+    }
+    let num_steps = u32::from_str_radix(&colour[..5], 16)
+        .map_err(|_| ParseError::at(line, &colour[..5], "5 hexadecimal digits"))?;
+    let dir = direction_from_str_digit(&colour[5..])
+        .ok_or_else(|| ParseError::at(line, &colour[5..], "'0', '1', '2' or '3'"))?;
+    Ok(Instruction { dir, num_steps })
 }
 
 fn calibrate_grid(instructions: &[Instruction]) -> (usize, usize, usize, usize) {
-    let mut p = (0i64, 0i64);
+    let mut p = Point::new(0, 0);
     let mut min = (0i64, 0i64);
     let mut max = (0i64, 0i64);
 
     for instruction in instructions {
-        match instruction.direction() {
-            Direction::East => p.1 += instruction.num_steps() as i64,
-            Direction::South => p.0 += instruction.num_steps() as i64,
-            Direction::West => p.1 -= instruction.num_steps() as i64,
-            Direction::North => p.0 -= instruction.num_steps() as i64,
-        }
-        if p.0 > max.0 {
-            max.0 = p.0;
-        }
-        if p.1 > max.1 {
-            max.1 = p.1;
-        }
-        if p.0 < min.0 {
-            min.0 = p.0;
-        }
-        if p.1 < min.1 {
-            min.1 = p.1;
-        }
+        p = p.moved(instruction.direction(), instruction.num_steps() as i64);
+        max = (max.0.max(p.row()), max.1.max(p.col()));
+        min = (min.0.min(p.row()), min.1.min(p.col()));
     }
 
     (
@@ -191,12 +206,10 @@ fn calibrate_grid(instructions: &[Instruction]) -> (usize, usize, usize, usize)
 pub fn dig(instructions: &[Instruction]) -> DigMap {
     let calibration = calibrate_grid(instructions);
     let mut m = DigMap {
-        content: vec![vec![Lagoon::Outside; calibration.3]; calibration.2],
-        num_rows: calibration.2,
-        num_cols: calibration.3,
+        content: Grid::new(calibration.2, calibration.3, Lagoon::Outside),
     };
 
-    let mut pos = Position::new(calibration.0, calibration.1, (calibration.2, calibration.3));
+    let mut pos = m.content.position(calibration.0, calibration.1);
     for instruction in instructions {
         m.dig_at(&pos, instruction.direction());
         let mut counter = 0u32;
@@ -217,7 +230,7 @@ pub fn dug_volume(instructions: &[Instruction]) -> u64 {
     let mut x_coordinates_set: HashSet<i64> = HashSet::new();
     let mut y_coordinates_set: HashSet<i64> = HashSet::new();
 
-    let mut pos = (0i64, 0i64);
+    let mut pos = Point::new(0, 0);
     for i in 0..instructions.len() {
         let previous = &instructions[if i == 0 {
             instructions.len() - 1
@@ -233,42 +246,36 @@ pub fn dug_volume(instructions: &[Instruction]) -> u64 {
         match current.direction() {
             // /!\ again, assuming clockwise direction
             Direction::East => {
-                let mut left = pos.1;
+                let mut left = pos.col();
                 if previous.direction() == Direction::South {
                     left += 1;
                 }
-                let mut right = pos.1 + current.num_steps() as i64;
+                let mut right = pos.col() + current.num_steps() as i64;
                 if next.direction() == Direction::South {
                     right += 1;
                 }
-                x_coordinates_set.insert(pos.0);
+                x_coordinates_set.insert(pos.row());
                 y_coordinates_set.insert(left);
                 y_coordinates_set.insert(right);
-                horizontal_boundaries.push((pos.0, left, right));
-                pos = (pos.0, pos.1 + current.num_steps() as i64);
+                horizontal_boundaries.push((pos.row(), left, right));
             }
             Direction::West => {
-                let mut left = pos.1 - current.num_steps() as i64;
+                let mut left = pos.col() - current.num_steps() as i64;
                 if next.direction() == Direction::South {
                     left += 1;
                 }
-                let mut right = pos.1;
+                let mut right = pos.col();
                 if previous.direction() == Direction::South {
                     right += 1;
                 }
-                x_coordinates_set.insert(pos.0 + 1);
+                x_coordinates_set.insert(pos.row() + 1);
                 y_coordinates_set.insert(left);
                 y_coordinates_set.insert(right);
-                horizontal_boundaries.push((pos.0 + 1, left, right));
-                pos = (pos.0, pos.1 - current.num_steps() as i64);
-            }
-            Direction::North => {
-                pos = (pos.0 - current.num_steps() as i64, pos.1);
-            }
-            Direction::South => {
-                pos = (pos.0 + current.num_steps() as i64, pos.1);
+                horizontal_boundaries.push((pos.row() + 1, left, right));
             }
+            _ => {}
         }
+        pos = pos.moved(current.direction(), current.num_steps() as i64);
     }
 
     let mut x_coordinates = Vec::from_iter(x_coordinates_set.iter());
@@ -285,9 +292,9 @@ pub fn dug_volume(instructions: &[Instruction]) -> u64 {
 
     let mut volume = 0u64;
     for i in 1..x_coordinates.len() {
-        let x_center = (x_coordinates[i] + x_coordinates[i - 1]) / 2;
+        let x_center = (x_coordinates[i] + x_coordinates[i - 1]).div_euclid(2);
         for j in 1..y_coordinates.len() {
-            let y_center = (y_coordinates[j] + y_coordinates[j - 1]) / 2;
+            let y_center = (y_coordinates[j] + y_coordinates[j - 1]).div_euclid(2);
 
             let mut inside = false;
             for b in horizontal_boundaries.as_slice() {
@@ -295,11 +302,7 @@ pub fn dug_volume(instructions: &[Instruction]) -> u64 {
                     continue;
                 }
                 if x_center >= b.0 {
-                    if inside {
-                        inside = false;
-                    } else {
-                        inside = true;
-                    }
+                    inside = !inside;
                 } else {
                     break;
                 }
@@ -385,6 +388,44 @@ U 2 (#7a21e3)";
         assert_eq!(instructions[13].num_steps(), 500254);
     }
 
+    #[test]
+    fn test_read_instructions_errors() {
+        let e = try_read_dig_plan("R 6 (#70c710)\nX 5 (#0dc571)")
+            .err()
+            .unwrap();
+        assert_eq!((e.line(), e.column()), (2, 1));
+
+        let e = try_read_dig_plan("R 6 (#70c710)\nD").err().unwrap();
+        assert_eq!((e.line(), e.column()), (2, 2));
+        assert_eq!(e.expected(), "number of steps");
+
+        let e = try_read_dig_plan_correctly("R 6 (#70c710)\nD 5 (#0dc575)")
+            .err()
+            .unwrap();
+        assert_eq!((e.line(), e.column()), (2, 12));
+
+        let e = try_read_dig_plan_correctly("R 6 (#70c71)").err().unwrap();
+        assert_eq!((e.line(), e.column()), (1, 7));
+    }
+
+    #[test]
+    fn test_stream_dig_plan() {
+        let instructions: Vec<Instruction> = stream_dig_plan(INPUT.as_bytes())
+            .map(|i| i.unwrap())
+            .collect();
+        assert_eq!(dug_volume(&instructions), 62);
+
+        let instructions: Vec<Instruction> = stream_dig_plan_correctly(INPUT.as_bytes())
+            .map(|i| i.unwrap())
+            .collect();
+        assert_eq!(dug_volume(&instructions), 952408144115);
+
+        match stream_dig_plan_correctly("R 6 (#70c710)\nD 5 (#0dc575)".as_bytes()).nth(1) {
+            Some(Err(ReadError::Parse(e))) => assert_eq!((e.line(), e.column()), (2, 12)),
+            _ => panic!("expected a parse error"),
+        }
+    }
+
     #[test]
     fn test_count_dug() {
         let instructions = read_dig_plan(&INPUT);
@@ -427,4 +468,11 @@ U 2 (#7a21e3)";
         ];
         assert_eq!(dug_volume(instructions.as_slice()), 300_000);
     }
+
+    #[test]
+    fn test_dig_low_memory_negative_odd_coordinates() {
+        let instructions = read_dig_plan("U 3 (#000000)\nR 1 (#000000)\nU 1 (#000000)\nR 1 (#000000)\nD 4 (#000000)\nL 2 (#000000)");
+        assert_eq!(dig(instructions.as_slice()).volume(), 14);
+        assert_eq!(dug_volume(instructions.as_slice()), 14);
+    }
 }
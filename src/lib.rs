@@ -1,12 +1,17 @@
 pub mod almanac;
+pub mod answers;
 pub mod beam_tracer;
+pub mod bench;
 pub mod calibration;
 pub mod camelcard;
 pub mod camelcard2;
 pub mod cube_game;
 pub mod desert;
+pub mod differential;
 pub mod engine_schematic;
-mod grid2d;
+pub mod error;
+pub mod generate;
+pub mod grid2d;
 pub mod hash;
 pub mod heatloss;
 pub mod hotsprings;
@@ -20,3 +25,5 @@ pub mod rocks;
 pub mod sandslabs;
 pub mod scratchcard;
 pub mod snowverload;
+pub mod solution;
+pub mod stream;
@@ -1,17 +1,463 @@
-use aoc2023::hotsprings::read_records;
+use aoc2023::answers::{Answers, Verdict};
+use aoc2023::bench::{self, Report};
+use aoc2023::calibration::CalibrationDecoder;
+use aoc2023::generate;
+use aoc2023::solution::{self, Day};
+use std::env;
 use std::fs;
+use std::process;
 use std::time::Instant;
 
-fn main() {
-    let input = fs::read_to_string("data/day12.txt").unwrap();
+const USAGE: &str = "usage: aoc2023 run [--day <n>] [--part <1|2>] [--input <path>] [--diagnose]
+       aoc2023 verify [--day <n>] [--part <1|2>] [--answers <path>]
+       aoc2023 record [--day <n>] [--part <1|2>] [--input <path>] [--answers <path>]
+       aoc2023 bench [--day <n>] [--input <path>] [--runs <n>] [--save <path>]
+                     [--compare <path>] [--tolerance <percent>]
+       aoc2023 generate --day <n> [--seed <n>] [--size <n>] [--output <path>]
+
+run: runs the solution of the given day and part, or of every day and both
+parts when they are omitted, leaving out the parts known to take minutes. The
+input defaults to data/day<n>.txt. With --diagnose, which requires --day 1 and
+applies to part 2 only, part 1 being unsolved, the digits found on each line of
+the calibration document are printed first.
+verify: runs the solutions and checks their answers against the answers file,
+data/answers.txt by default.
+record: runs the solutions and saves their answers into the answers file.
+bench: runs the solutions several times, 10 by default, and reports the min,
+median and max times of parsing and of each part, slow parts left out unless
+--day is given. The report can be saved, and compared to a saved baseline:
+medians more than the tolerance, 10% by default, above the baseline's are
+reported as regressions.
+generate: writes a random input for the given day, to the standard output by
+default. The same seed, 0 by default, always gives the same input; the size is
+a number of lines or the side of a grid, 20 by default.";
+
+const ANSWERS: &str = "data/answers.txt";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Run,
+    Verify,
+    Record,
+}
+
+struct RunOptions {
+    day: Option<&'static Day>,
+    part: Option<u32>,
+    input: Option<String>,
+    answers: String,
+    diagnose: bool,
+}
+
+struct BenchOptions {
+    day: Option<&'static Day>,
+    input: Option<String>,
+    runs: usize,
+    save: Option<String>,
+    compare: Option<String>,
+    tolerance: f64,
+}
+
+struct GenerateOptions {
+    day: u32,
+    seed: u64,
+    size: usize,
+    output: Option<String>,
+}
+
+fn parse_day(value: &str) -> Result<&'static Day, String> {
+    let number: u32 = value
+        .parse()
+        .map_err(|_| format!("invalid day: {}", value))?;
+    solution::day(number).ok_or_else(|| format!("no solution for day {}", number))
+}
+
+fn parse_run_options(args: &[String]) -> Result<RunOptions, String> {
+    let mut options = RunOptions {
+        day: None,
+        part: None,
+        input: None,
+        answers: ANSWERS.to_string(),
+        diagnose: false,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--diagnose" {
+            options.diagnose = true;
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", arg))?;
+        match arg.as_str() {
+            "--day" => options.day = Some(parse_day(value)?),
+            "--part" => {
+                options.part = match value.as_str() {
+                    "1" => Some(1),
+                    "2" => Some(2),
+                    _ => return Err(format!("invalid part: {}", value)),
+                }
+            }
+            "--input" => options.input = Some(value.clone()),
+            "--answers" => options.answers = value.clone(),
+            _ => return Err(format!("unknown option: {}", arg)),
+        }
+    }
+
+    if options.input.is_some() && options.day.is_none() {
+        return Err("--input requires --day".to_string());
+    }
+    if options.diagnose && options.day.map(|day| day.number()) != Some(1) {
+        return Err("--diagnose requires --day 1".to_string());
+    }
+    if options.diagnose && options.part == Some(1) {
+        return Err("--diagnose only applies to part 2".to_string());
+    }
+
+    Ok(options)
+}
+
+fn parse_bench_options(args: &[String]) -> Result<BenchOptions, String> {
+    let mut options = BenchOptions {
+        day: None,
+        input: None,
+        runs: 10,
+        save: None,
+        compare: None,
+        tolerance: 0.1,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", arg))?;
+        match arg.as_str() {
+            "--day" => options.day = Some(parse_day(value)?),
+            "--input" => options.input = Some(value.clone()),
+            "--runs" => {
+                options.runs = match value.parse() {
+                    Ok(runs) if runs > 0 => runs,
+                    _ => return Err(format!("invalid number of runs: {}", value)),
+                }
+            }
+            "--save" => options.save = Some(value.clone()),
+            "--compare" => options.compare = Some(value.clone()),
+            "--tolerance" => {
+                options.tolerance = match value.parse::<f64>() {
+                    Ok(percent) if percent >= 0.0 => percent / 100.0,
+                    _ => return Err(format!("invalid tolerance: {}", value)),
+                }
+            }
+            _ => return Err(format!("unknown option: {}", arg)),
+        }
+    }
+
+    if options.input.is_some() && options.day.is_none() {
+        return Err("--input requires --day".to_string());
+    }
+
+    Ok(options)
+}
+
+fn parse_generate_options(args: &[String]) -> Result<GenerateOptions, String> {
+    let mut day = None;
+    let mut options = GenerateOptions {
+        day: 0,
+        seed: 0,
+        size: 20,
+        output: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", arg))?;
+        match arg.as_str() {
+            "--day" => day = Some(parse_day(value)?.number()),
+            "--seed" => {
+                options.seed = value
+                    .parse()
+                    .map_err(|_| format!("invalid seed: {}", value))?
+            }
+            "--size" => {
+                options.size = match value.parse() {
+                    Ok(size) if size > 0 => size,
+                    _ => return Err(format!("invalid size: {}", value)),
+                }
+            }
+            "--output" => options.output = Some(value.clone()),
+            _ => return Err(format!("unknown option: {}", arg)),
+        }
+    }
+
+    options.day = day.ok_or_else(|| "generate requires --day".to_string())?;
+    Ok(options)
+}
+
+fn read_answers(path: &str) -> Result<Answers, String> {
+    match fs::read_to_string(path) {
+        Ok(input) => Answers::try_parse(&input).map_err(|e| format!("{}: {}", path, e)),
+        Err(_) => Ok(Answers::new()),
+    }
+}
+
+fn run(options: &RunOptions, mode: Mode) -> Result<(), String> {
+    let mut answers = match mode {
+        Mode::Run => Answers::new(),
+        _ => read_answers(&options.answers)?,
+    };
+    let mut num_failed = 0;
+
+    let days: Vec<&Day> = match options.day {
+        Some(day) => vec![day],
+        None => solution::days().iter().collect(),
+    };
+    let parts: Vec<u32> = match options.part {
+        Some(part) => vec![part],
+        None => vec![1, 2],
+    };
 
-    let mut line = 0usize;
     let now = Instant::now();
-    for record in read_records(&input) {
-        line += 1;
-        let count = record.unfold(4).count_arrangements();
-        println!("line {}: {}", line, count);
+    for day in days {
+        let path = match &options.input {
+            Some(path) => path.clone(),
+            None => format!("data/day{}.txt", day.number()),
+        };
+        let input = match fs::read_to_string(&path) {
+            Ok(input) => input,
+            Err(e) if options.day.is_some() => return Err(format!("{}: {}", path, e)),
+            Err(e) => {
+                println!("day {}: skipped ({}: {})", day.number(), path, e);
+                continue;
+            }
+        };
+
+        let start = Instant::now();
+        let solution = match day.parse(&input) {
+            Ok(solution) => solution,
+            Err(e) if options.day.is_some() => return Err(format!("{}: {}", path, e)),
+            Err(e) => {
+                println!("day {}: skipped ({}: {})", day.number(), path, e);
+                continue;
+            }
+        };
+        println!(
+            "day {} ({}): parsed in {:.2?}",
+            day.number(),
+            day.name(),
+            start.elapsed()
+        );
+        if options.diagnose {
+            print_diagnostics(&input);
+        }
+
+        for part in parts.iter() {
+            if options.day.is_none() && day.is_slow(*part) {
+                println!(
+                    "day {} part {}: skipped (slow, run it with --day {})",
+                    day.number(),
+                    part,
+                    day.number()
+                );
+                continue;
+            }
+            let start = Instant::now();
+            let answer = match part {
+                1 => solution.part1(),
+                _ => solution.part2(),
+            };
+            let elapsed = start.elapsed();
+            let answer = match answer {
+                Some(answer) => answer,
+                None => {
+                    println!("day {} part {}: not solved", day.number(), part);
+                    continue;
+                }
+            };
+            match mode {
+                Mode::Run => println!(
+                    "day {} part {}: {} ({:.2?})",
+                    day.number(),
+                    part,
+                    answer,
+                    elapsed
+                ),
+                Mode::Verify => match answers.verify(day.number(), *part, &answer) {
+                    Verdict::Pass => {
+                        println!("day {} part {}: pass ({:.2?})", day.number(), part, elapsed)
+                    }
+                    Verdict::Fail(expected) => {
+                        num_failed += 1;
+                        println!(
+                            "day {} part {}: FAIL, expected {}, found {} ({:.2?})",
+                            day.number(),
+                            part,
+                            expected,
+                            answer,
+                            elapsed
+                        )
+                    }
+                    Verdict::Unknown => println!(
+                        "day {} part {}: unknown, found {} ({:.2?})",
+                        day.number(),
+                        part,
+                        answer,
+                        elapsed
+                    ),
+                },
+                Mode::Record => {
+                    println!(
+                        "day {} part {}: recorded {} ({:.2?})",
+                        day.number(),
+                        part,
+                        answer,
+                        elapsed
+                    );
+                    answers.record(day.number(), *part, &answer);
+                }
+            }
+        }
+    }
+    println!("elapsed time is {:.2?}", now.elapsed());
+
+    if mode == Mode::Record {
+        fs::write(&options.answers, answers.to_string())
+            .map_err(|e| format!("{}: {}", options.answers, e))?;
+    }
+    if num_failed > 0 {
+        return Err(format!("{} answer(s) failed verification", num_failed));
+    }
+
+    Ok(())
+}
+
+fn print_diagnostics(document: &str) {
+    let reports = CalibrationDecoder::english().diagnose_document(document);
+    for report in reports.iter() {
+        println!("{}", report);
+    }
+    let num_missing = reports.iter().filter(|r| !r.has_digit()).count();
+    println!("{} line(s) without digit", num_missing);
+}
+
+fn run_bench(options: &BenchOptions) -> Result<(), String> {
+    let baseline = match &options.compare {
+        Some(path) => {
+            let input = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            Some(Report::try_parse(&input).map_err(|e| format!("{}: {}", path, e))?)
+        }
+        None => None,
+    };
+    let days: Vec<&Day> = match options.day {
+        Some(day) => vec![day],
+        None => solution::days().iter().collect(),
+    };
+
+    let mut report = Report::new();
+    for day in days {
+        let path = match &options.input {
+            Some(path) => path.clone(),
+            None => format!("data/day{}.txt", day.number()),
+        };
+        let day_report = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|input| {
+                let parts: Vec<u32> = [1, 2]
+                    .into_iter()
+                    .filter(|part| options.day.is_some() || !day.is_slow(*part))
+                    .collect();
+                bench::bench(day, &input, &parts, options.runs).map_err(|e| e.to_string())
+            });
+        let day_report = match day_report {
+            Ok(day_report) => day_report,
+            Err(e) if options.day.is_some() => return Err(format!("{}: {}", path, e)),
+            Err(e) => {
+                println!("day {}: skipped ({}: {})", day.number(), path, e);
+                continue;
+            }
+        };
+
+        for (number, stage, stats) in day_report.iter() {
+            let comparison = match baseline.as_ref().and_then(|b| b.get(number, stage)) {
+                Some(base) => format!(
+                    ", baseline median {:.2?} ({:+.1}%)",
+                    base.median(),
+                    (stats.median().as_secs_f64() / base.median().as_secs_f64() - 1.0) * 100.0
+                ),
+                None => String::new(),
+            };
+            println!(
+                "day {} {}: min {:.2?}, median {:.2?}, max {:.2?}{}",
+                number,
+                stage,
+                stats.min(),
+                stats.median(),
+                stats.max(),
+                comparison
+            );
+        }
+        report.merge(day_report);
+    }
+
+    if let Some(path) = &options.save {
+        fs::write(path, report.to_string()).map_err(|e| format!("{}: {}", path, e))?;
+    }
+    if let Some(baseline) = baseline {
+        let regressions = report.regressions(&baseline, options.tolerance);
+        for r in regressions.iter() {
+            println!(
+                "REGRESSION day {} {}: median {:.2?}, baseline {:.2?}",
+                r.day, r.stage, r.median, r.baseline
+            );
+        }
+        if !regressions.is_empty() {
+            return Err(format!("{} regression(s) found", regressions.len()));
+        }
+    }
+
+    Ok(())
+}
+
+fn run_generate(options: &GenerateOptions) -> Result<(), String> {
+    let input = generate::generate(options.day, options.seed, options.size)
+        .ok_or_else(|| format!("no generator for day {}", options.day))?;
+    match &options.output {
+        Some(path) => fs::write(path, input + "\n").map_err(|e| format!("{}: {}", path, e)),
+        None => {
+            println!("{}", input);
+            Ok(())
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.first().map(|s| s.as_str()) {
+        Some("run") => parse_run_options(&args[1..]).and_then(|options| run(&options, Mode::Run)),
+        Some("verify") => {
+            parse_run_options(&args[1..]).and_then(|options| run(&options, Mode::Verify))
+        }
+        Some("record") => {
+            parse_run_options(&args[1..]).and_then(|options| run(&options, Mode::Record))
+        }
+        Some("bench") => parse_bench_options(&args[1..]).and_then(|options| run_bench(&options)),
+        Some("generate") => {
+            parse_generate_options(&args[1..]).and_then(|options| run_generate(&options))
+        }
+        Some("help") | Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(command) => Err(format!("unknown command: {}\n\n{}", command, USAGE)),
+        None => Err(USAGE.to_string()),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
     }
-    let elapsed = now.elapsed();
-    println!("elapsed time is {}ms", elapsed.as_millis());
 }
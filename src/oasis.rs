@@ -1,3 +1,7 @@
+use crate::error::{parse_token, ParseError, ReadError};
+use crate::stream::parse_lines;
+use std::io::BufRead;
+
 pub fn extrapolate_next(values: &[i64]) -> i64 {
     let mut diff: Vec<i64> = Vec::with_capacity(values.len() - 1);
     for i in 1..values.len() {
@@ -24,31 +28,47 @@ pub fn extrapolate_previous(values: &[i64]) -> i64 {
     values.first().unwrap() - previous
 }
 
-fn read_series(input: &str) -> Vec<Vec<i64>> {
-    let mut series: Vec<Vec<i64>> = Vec::new();
-    for line in input.lines() {
-        let mut numbers: Vec<i64> = Vec::new();
-        line.split_whitespace()
-            .map(|str_num| str_num.parse::<i64>().unwrap())
-            .for_each(|n| numbers.push(n));
-        series.push(numbers);
-    }
+pub fn try_read_series(input: &str) -> Result<Vec<Vec<i64>>, ParseError> {
+    input
+        .lines()
+        .map(|line| try_parse_values(line).map_err(|e| e.within(input, line)))
+        .collect()
+}
 
-    series
+/// Lazily reads series of values, one per line.
+pub fn stream_series<R: BufRead>(reader: R) -> impl Iterator<Item = Result<Vec<i64>, ReadError>> {
+    parse_lines(reader, try_parse_values)
+}
+
+fn try_parse_values(line: &str) -> Result<Vec<i64>, ParseError> {
+    let mut numbers: Vec<i64> = Vec::new();
+    for str_num in line.split_whitespace() {
+        numbers.push(parse_token(line, str_num, "number")?);
+    }
+    if numbers.is_empty() {
+        return Err(ParseError::at(line, line, "number"));
+    }
+    Ok(numbers)
 }
 
 pub fn sum_extrapolated_next_values(input: &str) -> i64 {
-    let series = read_series(input);
+    let series = try_read_series(input).unwrap_or_else(|e| panic!("{}", e));
+    sum_extrapolated_next_values_of(&series)
+}
 
+pub fn sum_extrapolated_previous_values(input: &str) -> i64 {
+    let series = try_read_series(input).unwrap_or_else(|e| panic!("{}", e));
+    sum_extrapolated_previous_values_of(&series)
+}
+
+pub fn sum_extrapolated_next_values_of(series: &[Vec<i64>]) -> i64 {
     series
         .iter()
         .map(|values| extrapolate_next(values.as_slice()))
         .sum()
 }
 
-pub fn sum_extrapolated_previous_values(input: &str) -> i64 {
-    let series = read_series(input);
-
+pub fn sum_extrapolated_previous_values_of(series: &[Vec<i64>]) -> i64 {
     series
         .iter()
         .map(|values| extrapolate_previous(values.as_slice()))
@@ -79,6 +99,26 @@ mod tests {
         assert_eq!(next, 68);
     }
 
+    #[test]
+    fn test_read_series_errors() {
+        let e = try_read_series("0 3 6\n1 3 6.5").unwrap_err();
+        assert_eq!((e.line(), e.column()), (2, 5));
+    }
+
+    #[test]
+    fn test_stream_series() {
+        let sum: Result<i64, ReadError> =
+            stream_series("0 3 6 9 12 15\r\n1 3 6 10 15 21\n".as_bytes())
+                .map(|values| values.map(|values| extrapolate_next(&values)))
+                .sum();
+        assert_eq!(sum.unwrap(), 46);
+
+        match stream_series("0 3 6\n\n1 3".as_bytes()).nth(1) {
+            Some(Err(ReadError::Parse(e))) => assert_eq!(e.line(), 2),
+            _ => panic!("expected a parse error"),
+        }
+    }
+
     #[test]
     fn test_sum_extrapolated_next_values() {
         assert_eq!(
@@ -1,77 +1,54 @@
-use crate::grid2d::Position;
+use crate::error::{Found, ParseError};
+use crate::grid2d::{Grid, Position};
 use std::fmt;
 
 pub struct SpaceMap {
-    size: (usize, usize),
-    content: Vec<Vec<bool>>,
+    content: Grid<bool>,
     expanded_rows: Vec<usize>,
     expanded_cols: Vec<usize>,
 }
 
 impl SpaceMap {
     pub fn parse(input: &str) -> SpaceMap {
+        SpaceMap::try_parse(input).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_parse(input: &str) -> Result<SpaceMap, ParseError> {
         let mut m = SpaceMap {
-            size: (0, 0),
-            content: Vec::new(),
+            content: Grid::try_parse_with(input, |c| match c {
+                '.' => Ok(false),
+                '#' => Ok(true),
+                _ => Err(ParseError::new(
+                    1,
+                    1,
+                    "'.' or '#'",
+                    Found::Token(c.to_string()),
+                )),
+            })?,
             expanded_rows: Vec::new(),
             expanded_cols: Vec::new(),
         };
-
-        for line in input.lines() {
-            let mut row: Vec<bool> = Vec::new();
-            for c in line.chars() {
-                let v = match c {
-                    '.' => false,
-                    '#' => true,
-                    _ => panic!("unexpected character"),
-                };
-                row.push(v);
-            }
-            m.content.push(row);
-        }
-        m.size = (m.content.len(), m.content[0].len());
-
-        for i in 0..m.size.0 {
-            let mut has_galaxies = false;
-            for j in 0..m.size.1 {
-                if m.content[i][j] {
-                    has_galaxies = true;
-                    break;
-                }
-            }
-            if !has_galaxies {
+        for (i, row) in m.content.rows().enumerate() {
+            if !row.contains(&true) {
                 m.expanded_rows.push(i);
             }
         }
 
-        for i in 0..m.size.1 {
-            let mut has_galaxies = false;
-            for j in 0..m.size.0 {
-                if m.content[j][i] {
-                    has_galaxies = true;
-                    break;
-                }
-            }
-            if !has_galaxies {
-                m.expanded_cols.push(i);
+        for (j, mut column) in m.content.columns().enumerate() {
+            if !column.any(|galaxy| *galaxy) {
+                m.expanded_cols.push(j);
             }
         }
 
-        m
+        Ok(m)
     }
 
     fn galaxies(&self) -> Vec<Position> {
-        let mut positions: Vec<Position> = Vec::new();
-
-        for i in 0..self.size.0 {
-            for j in 0..self.size.1 {
-                if self.content[i][j] {
-                    positions.push(Position::new(i, j, self.size));
-                }
-            }
-        }
-
-        positions
+        self.content
+            .iter()
+            .filter(|(_, galaxy)| **galaxy)
+            .map(|(position, _)| position)
+            .collect()
     }
 
     fn num_expanded_rows(&self, start: usize, end: usize) -> usize {
@@ -102,14 +79,14 @@ impl SpaceMap {
         } else {
             (y.row(), x.row())
         };
-        let x_dist = max_x - min_x + self.num_expanded_rows(min_x, max_x) * (expansion-1);
+        let x_dist = max_x - min_x + self.num_expanded_rows(min_x, max_x) * (expansion - 1);
 
         let (max_y, min_y) = if x.col() > y.col() {
             (x.col(), y.col())
         } else {
             (y.col(), x.col())
         };
-        let y_dist = max_y - min_y + self.num_expanded_cols(min_y, max_y) * (expansion-1);
+        let y_dist = max_y - min_y + self.num_expanded_cols(min_y, max_y) * (expansion - 1);
 
         x_dist + y_dist
     }
@@ -129,20 +106,14 @@ impl SpaceMap {
 
 impl fmt::Display for SpaceMap {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut s = "".to_string();
-        for i in 0..self.size.0 {
-            for j in 0..self.size.1 {
-                let c = match self.content[i][j] {
-                    true => '#',
-                    false => '.',
-                };
-                s.push(c);
-            }
-            if i < self.size.0 - 1 {
-                s.push('\n');
-            }
-        }
-        write!(f, "{}", s)
+        write!(
+            f,
+            "{}",
+            self.content.render(|galaxy| match galaxy {
+                true => '#',
+                false => '.',
+            })
+        )
     }
 }
 
@@ -168,6 +139,13 @@ mod tests {
         assert_eq!(INPUT, output);
     }
 
+    #[test]
+    fn test_parse_space_map_error() {
+        let e = SpaceMap::try_parse("...#\n.#o.").err().unwrap();
+        assert_eq!((e.line(), e.column()), (2, 3));
+        assert_eq!(e.found(), &Found::Token("o".to_string()));
+    }
+
     #[test]
     fn test_galaxies() {
         let m = SpaceMap::parse(&INPUT);
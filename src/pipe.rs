@@ -1,4 +1,5 @@
-use crate::grid2d::{Direction, Position};
+use crate::error::{Found, ParseError};
+use crate::grid2d::{Direction, Grid, Position};
 
 #[derive(PartialEq, Clone, Copy, Debug)]
 pub enum Tile {
@@ -13,7 +14,7 @@ pub enum Tile {
 }
 
 impl TryFrom<char> for Tile {
-    type Error = &'static str;
+    type Error = ParseError;
 
     fn try_from(c: char) -> Result<Self, Self::Error> {
         match c {
@@ -25,14 +26,19 @@ impl TryFrom<char> for Tile {
             '7' => Ok(Tile::SouthWest),
             'F' => Ok(Tile::SouthEast),
             'S' => Ok(Tile::Start),
-            _ => Err("invalid pipe character"),
+            _ => Err(ParseError::new(
+                1,
+                1,
+                "pipe ('.', '|', '-', 'L', 'J', '7', 'F' or 'S')",
+                Found::Token(c.to_string()),
+            )),
         }
     }
 }
 
-impl Into<char> for Tile {
-    fn into(self) -> char {
-        match self {
+impl From<Tile> for char {
+    fn from(tile: Tile) -> char {
+        match tile {
             Tile::Ground => '.',
             Tile::NorthSouth => '|',
             Tile::EastWest => '-',
@@ -46,68 +52,67 @@ impl Into<char> for Tile {
 }
 
 pub struct TileMap {
-    tiles: Vec<Vec<Tile>>,
+    tiles: Grid<Tile>,
     start: (usize, usize),
-    size: (usize, usize),
 }
 
 impl TileMap {
     pub fn parse(input: &str) -> TileMap {
-        let mut m = TileMap {
-            tiles: Vec::new(),
-            start: (0, 0),
-            size: (0, 0),
-        };
-
-        let mut row = 0usize;
-        let mut column = 0usize;
-        for line in input.lines() {
-            let mut pipe_line: Vec<Tile> = Vec::new();
-            for c in line.chars() {
-                let p = Tile::try_from(c).unwrap();
-                if p == Tile::Start {
-                    m.start = (row, column);
+        TileMap::try_parse(input).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_parse(input: &str) -> Result<TileMap, ParseError> {
+        let tiles: Grid<Tile> = Grid::try_parse(input)?;
+
+        let mut start: Option<(usize, usize)> = None;
+        for (row, line) in input.lines().enumerate() {
+            for (column, (i, c)) in line.char_indices().enumerate() {
+                if tiles[(row, column)] != Tile::Start {
+                    continue;
                 }
-                pipe_line.push(Tile::try_from(c).unwrap());
-                column += 1;
+                if start.is_some() {
+                    return Err(ParseError::at(
+                        input,
+                        &line[i..i + c.len_utf8()],
+                        "single 'S'",
+                    ));
+                }
+                start = Some((row, column));
             }
-            m.tiles.push(pipe_line);
-            row += 1;
-            column = 0;
         }
+        let start = start.ok_or_else(|| ParseError::at_end(input, "'S'"))?;
 
-        m.size = (m.tiles.len(), m.tiles[0].len());
-        m
+        Ok(TileMap { tiles, start })
     }
 
     fn get(&self, p: Position) -> Tile {
-        self.tiles[p.row()][p.col()]
+        self.tiles[p]
     }
 
     fn start(&self) -> Position {
-        Position::new(self.start.0, self.start.1, self.size)
+        self.tiles.position(self.start.0, self.start.1)
     }
 
     fn start_tile(&self) -> Tile {
         let mut north = false;
         if self.start.0 > 0 {
-            let north_tile = &self.tiles[self.start.0 - 1][self.start.1];
+            let north_tile = &self.tiles[(self.start.0 - 1, self.start.1)];
             north = *north_tile == Tile::NorthSouth
                 || *north_tile == Tile::SouthEast
                 || *north_tile == Tile::SouthWest;
         }
 
         let mut east = false;
-        if self.start.1 < self.size.1 - 1 {
-            let east_tile = &self.tiles[self.start.0][self.start.1 + 1];
+        if self.start.1 < self.tiles.num_cols() - 1 {
+            let east_tile = &self.tiles[(self.start.0, self.start.1 + 1)];
             east = *east_tile == Tile::EastWest
                 || *east_tile == Tile::NorthWest
                 || *east_tile == Tile::SouthWest;
         }
 
         let mut south = false;
-        if self.start.0 < self.size.0 - 1 {
-            let south_tile = &self.tiles[self.start.0 + 1][self.start.1];
+        if self.start.0 < self.tiles.num_rows() - 1 {
+            let south_tile = &self.tiles[(self.start.0 + 1, self.start.1)];
             south = *south_tile == Tile::NorthEast
                 || *south_tile == Tile::NorthSouth
                 || *south_tile == Tile::NorthWest;
@@ -115,7 +120,7 @@ impl TileMap {
 
         let mut west = false;
         if self.start.1 > 0 {
-            let west_tile = &self.tiles[self.start.0][self.start.1 - 1];
+            let west_tile = &self.tiles[(self.start.0, self.start.1 - 1)];
             west = *west_tile == Tile::SouthEast
                 || *west_tile == Tile::EastWest
                 || *west_tile == Tile::NorthEast;
@@ -226,12 +231,12 @@ pub fn loop_size(map: &TileMap) -> u64 {
     let mut distance = 0u64;
 
     let mut previous = map.start();
-    let mut position = next_position_from_start(&map);
+    let mut position = next_position_from_start(map);
     distance += 1;
 
     while position != map.start() {
         let tmp = position;
-        position = next_position(&map, previous, position);
+        position = next_position(map, previous, position);
         previous = tmp;
         distance += 1;
     }
@@ -239,60 +244,42 @@ pub fn loop_size(map: &TileMap) -> u64 {
     distance
 }
 
-pub fn collect_boundary(map: &TileMap) -> Vec<Vec<Option<Tile>>> {
-    let mut boundary: Vec<Vec<Option<Tile>>> = Vec::with_capacity(map.size.0);
-    for _ in 0..map.size.0 {
-        let mut row: Vec<Option<Tile>> = Vec::with_capacity(map.size.1);
-        for _ in 0..map.size.1 {
-            row.push(None);
-        }
-        boundary.push(row);
-    }
+pub fn collect_boundary(map: &TileMap) -> Grid<Option<Tile>> {
+    let mut boundary: Grid<Option<Tile>> =
+        Grid::new(map.tiles.num_rows(), map.tiles.num_cols(), None);
 
     let mut from = map.start();
-    let mut position = next_position_from_start(&map);
-    boundary[position.row()][position.col()] = Some(map.get(position).clone());
+    let mut position = next_position_from_start(map);
+    boundary[position] = Some(map.get(position));
 
     while position != map.start() {
         let tmp = position;
-        position = next_position(&map, from, position);
+        position = next_position(map, from, position);
         from = tmp;
-        boundary[position.row()][position.col()] = Some(map.get(position));
+        boundary[position] = Some(map.get(position));
     }
 
     let start_tile = map.start_tile();
-    boundary[map.start.0][map.start.1] = Some(start_tile);
+    boundary[map.start] = Some(start_tile);
 
     boundary
 }
 
-pub fn count_enclosed_tiles(map: &TileMap) -> (u64, Vec<Vec<Option<Tile>>>) {
-    let boundary = collect_boundary(&map);
-
-    let mut debug: Vec<Vec<Option<Tile>>> = Vec::with_capacity(boundary.len());
-    for i in 0..boundary.len() {
-        let mut row = Vec::with_capacity(boundary[0].len());
-        for j in 0..boundary[0].len() {
-            row.push(boundary[i][j]);
-        }
-        debug.push(row);
-    }
+pub fn count_enclosed_tiles(map: &TileMap) -> (u64, Grid<Option<Tile>>) {
+    let boundary = collect_boundary(map);
+    let mut debug = boundary.clone();
 
     let mut count = 0u64;
 
-    for row in 0..map.size.0 {
+    for row in 0..boundary.num_rows() {
         let mut enclosed = false;
-        for column in 0..map.size.1 {
-            let tile = &boundary[row][column];
+        for column in 0..boundary.num_cols() {
+            let tile = &boundary[(row, column)];
             if *tile == Some(Tile::SouthWest)
                 || *tile == Some(Tile::NorthSouth)
                 || *tile == Some(Tile::SouthEast)
             {
-                if enclosed {
-                    enclosed = false;
-                } else {
-                    enclosed = true;
-                }
+                enclosed = !enclosed;
                 continue;
             }
             if tile.is_some() {
@@ -300,7 +287,7 @@ pub fn count_enclosed_tiles(map: &TileMap) -> (u64, Vec<Vec<Option<Tile>>>) {
             }
             if enclosed {
                 count += 1;
-                debug[row][column] = Some(Tile::Ground);
+                debug[(row, column)] = Some(Tile::Ground);
             }
         }
     }
@@ -308,25 +295,19 @@ pub fn count_enclosed_tiles(map: &TileMap) -> (u64, Vec<Vec<Option<Tile>>>) {
     (count, debug)
 }
 
-pub fn print_boundary(boundary: &Vec<Vec<Option<Tile>>>) {
-    let mut s = String::new();
+pub fn print_boundary(boundary: &Grid<Option<Tile>>) {
+    let s = boundary.render(|tile| match tile {
+        None => ' ',
+        Some(tile) => (*tile).into(),
+    });
 
-    for i in 0..boundary.len() {
-        for j in 0..boundary[0].len() {
-            match boundary[i][j] {
-                None => s.push(' '),
-                Some(i) => s.push(i.into()),
-            }
-        }
-        s.push('\n');
-    }
-
-    println!("{}", s);
+    println!("{}\n", s);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::Found;
 
     #[test]
     fn test_simple_loop_size() {
@@ -369,6 +350,22 @@ LJ.LJ";
         assert_eq!(a.start_tile(), Tile::NorthWest);
     }
 
+    #[test]
+    fn test_parse_errors() {
+        let e = TileMap::try_parse(".F7\n.S|\n.LX").err().unwrap();
+        assert_eq!((e.line(), e.column()), (3, 3));
+
+        let e = TileMap::try_parse(".F7\n.S\n.LJ").err().unwrap();
+        assert_eq!((e.line(), e.column()), (2, 3));
+        assert_eq!(e.found(), &Found::EndOfLine);
+
+        let e = TileMap::try_parse(".F7\n.||\n.LJ").err().unwrap();
+        assert_eq!(e.expected(), "'S'");
+
+        let e = TileMap::try_parse(".F7\nSS|\n.LJ").err().unwrap();
+        assert_eq!((e.line(), e.column()), (2, 2));
+    }
+
     #[test]
     fn test_count_enclosed_tiles_1() {
         let input = "...........
@@ -1,59 +1,97 @@
+use crate::error::{parse_token, ParseError};
 use num_integer::Roots;
 
 pub fn prod_num_possibilities(input: &str) -> u64 {
-    let mut times: Vec<u64> = Vec::new();
-    let mut distances: Vec<u64> = Vec::new();
+    let races = try_read_races(input).unwrap_or_else(|e| panic!("{}", e));
+    prod_num_possibilities_of(&races)
+}
 
-    let mut lines = input.lines();
-    let line_times = lines.next().unwrap();
-    let mut first_digit_index = line_times.find(|c: char| c.is_digit(10)).unwrap();
-    line_times[first_digit_index..]
-        .split_whitespace()
-        .map(|str_num| str_num.parse::<u64>().unwrap())
-        .for_each(|time| times.push(time));
-
-    let line_dists = lines.next().unwrap();
-    first_digit_index = line_dists.find(|c: char| c.is_digit(10)).unwrap();
-    line_dists[first_digit_index..]
-        .split_whitespace()
-        .map(|str_num| str_num.parse::<u64>().unwrap())
-        .for_each(|dist| distances.push(dist));
+pub fn num_possibilities(input: &str) -> u64 {
+    let race = try_read_race(input).unwrap_or_else(|e| panic!("{}", e));
+    num_possibilities_of(race)
+}
 
+pub fn prod_num_possibilities_of(races: &[(u64, u64)]) -> u64 {
     let mut prod: u64 = 1;
-    for i in 0..times.len() {
-        let (lower, upper) = range(times[i], distances[i]);
+    for &(time, dist) in races {
+        let (lower, upper) = range(time, dist);
         prod *= upper - lower + 1;
     }
     prod
 }
 
-pub fn num_possibilities(input: &str) -> u64 {
-    let mut lines = input.lines();
-    let line_times = lines.next().unwrap();
-    let mut first_digit_index = line_times.find(|c: char| c.is_digit(10)).unwrap();
-    let mut str_time: String = "".to_string();
-    line_times[first_digit_index..]
-        .split_whitespace()
-        .for_each(|s| str_time.push_str(s));
-    let time = str_time.parse::<u64>().unwrap();
-
-    let line_dists = lines.next().unwrap();
-    first_digit_index = line_dists.find(|c: char| c.is_digit(10)).unwrap();
-    let mut str_dist: String = "".to_string();
-    line_dists[first_digit_index..]
-        .split_whitespace()
-        .for_each(|s| str_dist.push_str(s));
-    let dist = str_dist.parse::<u64>().unwrap();
-
+pub fn num_possibilities_of((time, dist): (u64, u64)) -> u64 {
     let (lower, upper) = range_safer(time, dist);
     upper - lower + 1
 }
 
+/// Reads the (time, record distance) pairs of exercise 6.1.
+pub fn try_read_races(input: &str) -> Result<Vec<(u64, u64)>, ParseError> {
+    let (str_times, str_dists) = try_read_sheet(input)?;
+
+    let mut times: Vec<u64> = Vec::new();
+    for str_num in str_times.split_whitespace() {
+        times.push(parse_token(input, str_num, "time")?);
+    }
+    let mut distances: Vec<u64> = Vec::new();
+    for str_num in str_dists.split_whitespace() {
+        distances.push(parse_token(input, str_num, "distance")?);
+    }
+    if distances.len() < times.len() {
+        return Err(ParseError::at(
+            input,
+            &str_dists[str_dists.len()..],
+            "distance",
+        ));
+    }
+    if distances.len() > times.len() {
+        let extra = str_dists.split_whitespace().nth(times.len()).unwrap();
+        return Err(ParseError::at(input, extra, "end of line"));
+    }
+
+    Ok(times.into_iter().zip(distances).collect())
+}
+
+/// Reads the single race of exercise 6.2, ignoring the spaces between digits.
+pub fn try_read_race(input: &str) -> Result<(u64, u64), ParseError> {
+    let (str_time, str_dist) = try_read_sheet(input)?;
+    Ok((
+        parse_kerned(input, str_time, "time")?,
+        parse_kerned(input, str_dist, "distance")?,
+    ))
+}
+
+fn try_read_sheet(input: &str) -> Result<(&str, &str), ParseError> {
+    let mut lines = input.lines().map(|line| line.trim());
+    let line_times = lines
+        .next()
+        .ok_or_else(|| ParseError::at_end(input, "'Time:'"))?;
+    let str_times = line_times
+        .strip_prefix("Time:")
+        .ok_or_else(|| ParseError::at(input, line_times, "'Time:'"))?;
+    let line_dists = lines
+        .next()
+        .ok_or_else(|| ParseError::at_end(input, "'Distance:'"))?;
+    let str_dists = line_dists
+        .strip_prefix("Distance:")
+        .ok_or_else(|| ParseError::at(input, line_dists, "'Distance:'"))?;
+
+    Ok((str_times, str_dists))
+}
+
+fn parse_kerned(input: &str, s: &str, expected: &str) -> Result<u64, ParseError> {
+    let mut str_num: String = "".to_string();
+    s.split_whitespace().for_each(|part| str_num.push_str(part));
+    str_num
+        .parse::<u64>()
+        .map_err(|_| ParseError::at(input, s.trim(), expected))
+}
+
 fn distance(time: u64, speed: u64) -> u64 {
     (time - speed) * speed
 }
 
-fn range(time: u64, dist: u64) -> (u64, u64) {
+pub(crate) fn range(time: u64, dist: u64) -> (u64, u64) {
     let f_time = time as f64;
     let f_dist = dist as f64;
     let f_range = f_time * (1.0 - 4.0 * f_dist / f_time / f_time).sqrt();
@@ -73,7 +111,7 @@ fn range(time: u64, dist: u64) -> (u64, u64) {
 }
 
 /// Similar to range but avoids the dangerous conversion u64 -> f64 -> u64.
-fn range_safer(time: u64, dist: u64) -> (u64, u64) {
+pub(crate) fn range_safer(time: u64, dist: u64) -> (u64, u64) {
     let range = (time * time - 4 * dist).sqrt() + 1; // slightly overestimate the range
     let mut lower = (time - range) / 2;
     let mut upper = (time + range) / 2;
@@ -109,6 +147,16 @@ mod tests {
         assert_eq!(upper - lower + 1, 9);
     }
 
+    #[test]
+    fn test_read_races_errors() {
+        let e = try_read_races("Time:      7  15   30\nDistance:  9  40").unwrap_err();
+        assert_eq!((e.line(), e.column()), (2, 17));
+        assert_eq!(e.expected(), "distance");
+
+        let e = try_read_race("Time:      7  15   30\nDist:  9  40  200").unwrap_err();
+        assert_eq!((e.line(), e.column()), (2, 1));
+    }
+
     #[test]
     fn test_prod_num_possibilities() {
         assert_eq!(
@@ -1,26 +1,71 @@
-pub fn summarize(input: &str) -> u64 {
-    let mut summary = 0u64;
-
-    let mut lines = input.lines();
-    while let Some(mut line) = lines.next() {
-        let mut m: Vec<Vec<char>> = Vec::new();
-        while !line.is_empty() {
-            let mut row: Vec<char> = Vec::new();
-            for c in line.chars() {
-                row.push(c);
-            }
-            m.push(row);
+use crate::error::{Found, ParseError};
+use crate::grid2d::Grid;
+
+/// A pattern of ash (`.`) and rocks (`#`), all rows having the same length.
+pub struct Pattern {
+    rows: Vec<Vec<char>>,
+}
+
+impl Pattern {
+    pub fn parse(input: &str) -> Pattern {
+        Pattern::try_parse(input).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_parse(input: &str) -> Result<Pattern, ParseError> {
+        let grid = Grid::try_parse_with(input, |c| match c {
+            '.' | '#' => Ok(c),
+            _ => Err(ParseError::new(
+                1,
+                1,
+                "'.' or '#'",
+                Found::Token(c.to_string()),
+            )),
+        })?;
+        Ok(Pattern {
+            rows: grid.rows().map(|row| row.to_vec()).collect(),
+        })
+    }
+
+    /// Returns the number of columns left of the vertical mirror, or 100
+    /// times the number of rows above the horizontal one.
+    pub fn summary(&self) -> u64 {
+        summarize_one_map(&self.rows)
+    }
 
-            let line_opt = lines.next();
-            if line_opt.is_none() {
-                break;
+    /// Returns the summary of the mirror found once one smudge is fixed.
+    pub fn summary_with_smudge(&self) -> u64 {
+        fix_smudge_and_summarize_one_map(&self.rows)
+    }
+}
+
+/// Reads patterns separated by empty lines.
+pub fn try_read_patterns(input: &str) -> Result<Vec<Pattern>, ParseError> {
+    let mut patterns = Vec::new();
+    let mut block: Option<(usize, usize)> = None;
+    for line in input.lines().map(Some).chain([None]) {
+        match (line.filter(|line| !line.is_empty()), block) {
+            (Some(line), _) => {
+                let start = line.as_ptr() as usize - input.as_ptr() as usize;
+                let first = block.map_or(start, |(first, _)| first);
+                block = Some((first, start + line.len()));
+            }
+            (None, Some((first, last))) => {
+                let text = &input[first..last];
+                patterns.push(Pattern::try_parse(text).map_err(|e| e.within(input, text))?);
+                block = None;
             }
-            line = line_opt.unwrap();
+            (None, None) => {}
         }
-        summary += summarize_one_map(&m);
     }
+    Ok(patterns)
+}
 
-    summary
+pub fn summarize(input: &str) -> u64 {
+    try_read_patterns(input)
+        .unwrap_or_else(|e| panic!("{}", e))
+        .iter()
+        .map(Pattern::summary)
+        .sum()
 }
 
 fn summarize_one_map(m: &Vec<Vec<char>>) -> u64 {
@@ -73,28 +118,11 @@ fn summarize_one_map(m: &Vec<Vec<char>>) -> u64 {
 }
 
 pub fn fix_smudges_and_summarize(input: &str) -> u64 {
-    let mut summary = 0u64;
-
-    let mut lines = input.lines();
-    while let Some(mut line) = lines.next() {
-        let mut m: Vec<Vec<char>> = Vec::new();
-        while !line.is_empty() {
-            let mut row: Vec<char> = Vec::new();
-            for c in line.chars() {
-                row.push(c);
-            }
-            m.push(row);
-
-            let line_opt = lines.next();
-            if line_opt.is_none() {
-                break;
-            }
-            line = line_opt.unwrap();
-        }
-        summary += fix_smudge_and_summarize_one_map(&m);
-    }
-
-    summary
+    try_read_patterns(input)
+        .unwrap_or_else(|e| panic!("{}", e))
+        .iter()
+        .map(Pattern::summary_with_smudge)
+        .sum()
 }
 
 fn fix_smudge_and_summarize_one_map(m: &Vec<Vec<char>>) -> u64 {
@@ -173,5 +201,24 @@ mod tests {
 #....#..#
 ";
         assert_eq!(summarize(&input), 405);
+        assert_eq!(fix_smudges_and_summarize(&input), 400);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let e = try_read_patterns("#.#\n.#.\n\n##.\n#.").err().unwrap();
+        assert_eq!((e.line(), e.column()), (5, 3));
+        assert_eq!(e.expected(), "grid cell");
+
+        let e = try_read_patterns("#.#\n.#.#\n").err().unwrap();
+        assert_eq!((e.line(), e.column()), (2, 4));
+        assert_eq!(e.expected(), "end of line");
+
+        let e = try_read_patterns("#.#\n\n\n.x.").err().unwrap();
+        assert_eq!((e.line(), e.column()), (4, 2));
+        assert_eq!(e.expected(), "'.' or '#'");
+
+        assert_eq!(try_read_patterns("\n#.\n##\n\n").unwrap().len(), 1);
+        assert_eq!(Pattern::parse("#.\n#.").summary(), 100);
     }
 }
@@ -1,3 +1,5 @@
+use crate::error::{Found, ParseError};
+use crate::grid2d::Grid;
 use std::{collections::HashMap, fmt};
 
 #[derive(Clone, Debug, PartialEq)]
@@ -7,20 +9,25 @@ pub enum Rock {
 }
 
 impl TryFrom<char> for Rock {
-    type Error = &'static str;
+    type Error = ParseError;
 
     fn try_from(c: char) -> Result<Self, Self::Error> {
         match c {
             'O' => Ok(Rock::Rounded),
             '#' => Ok(Rock::Squared),
-            _ => Err("invalid Rock character"),
+            _ => Err(ParseError::new(
+                1,
+                1,
+                "'O' or '#'",
+                Found::Token(c.to_string()),
+            )),
         }
     }
 }
 
-impl Into<char> for &Rock {
-    fn into(self) -> char {
-        match self {
+impl From<&Rock> for char {
+    fn from(rock: &Rock) -> char {
+        match rock {
             Rock::Rounded => 'O',
             Rock::Squared => '#',
         }
@@ -29,66 +36,31 @@ impl Into<char> for &Rock {
 
 #[derive(PartialEq)]
 pub struct Dish {
-    size: (usize, usize),
-    content: Vec<Vec<Option<Rock>>>,
+    content: Grid<Option<Rock>>,
 }
 
 impl Dish {
     pub fn parse(input: &str) -> Dish {
-        let mut d = Dish {
-            size: (0, 0),
-            content: Vec::new(),
-        };
-
-        for line in input.lines() {
-            let mut row: Vec<Option<Rock>> = Vec::new();
-            for c in line.chars() {
-                row.push(if let Ok(rock) = Rock::try_from(c) {
-                    Some(rock)
-                } else {
-                    None
-                });
-            }
-            d.content.push(row);
-        }
-
-        d.size = if d.content.len() == 0 {
-            (0, 0)
-        } else {
-            (d.content.len(), d.content[0].len())
-        };
-
-        d
+        Dish::try_parse(input).unwrap_or_else(|e| panic!("{}", e))
     }
 
-    fn to_string(&self) -> String {
-        let mut s = String::with_capacity(self.size.0 * (self.size.1 + 1));
-        for i in 0..self.size.0 {
-            for j in 0..self.size.1 {
-                s.push(if let Some(rock) = &self.content[i][j] {
-                    rock.into()
-                } else {
-                    '.'
-                });
-            }
-            if i < self.size.0 - 1 {
-                s.push('\n');
-            }
-        }
-        s
+    pub fn try_parse(input: &str) -> Result<Dish, ParseError> {
+        let content = Grid::try_parse_with(input, |c| match c {
+            '.' => Ok(None),
+            _ => Rock::try_from(c)
+                .map(Some)
+                .map_err(|_| ParseError::new(1, 1, "'.', 'O' or '#'", Found::Token(c.to_string()))),
+        })?;
+
+        Ok(Dish { content })
     }
 
     pub fn load(&self) -> u64 {
         let mut load = 0usize;
 
-        for i in 0..self.size.0 {
-            for j in 0..self.size.1 {
-                if let Some(rock) = &self.content[i][j] {
-                    load += match rock {
-                        Rock::Rounded => self.size.0 - i,
-                        Rock::Squared => 0,
-                    };
-                }
+        for (position, cell) in self.content.iter() {
+            if cell == &Some(Rock::Rounded) {
+                load += self.content.num_rows() - position.row();
             }
         }
 
@@ -97,14 +69,13 @@ impl Dish {
 
     fn slide_north(&self) -> Dish {
         let mut d = Dish {
-            size: self.size,
-            content: vec![vec![None; self.size.1]; self.size.0],
+            content: Grid::new(self.content.num_rows(), self.content.num_cols(), None),
         };
 
-        for j in 0..self.size.1 {
+        for j in 0..self.content.num_cols() {
             let mut next_available_slot: Option<usize> = None;
-            for i in 0..self.size.0 {
-                match self.content[i][j] {
+            for i in 0..self.content.num_rows() {
+                match self.content[(i, j)] {
                     Some(Rock::Rounded) => {
                         let row = if let Some(index) = next_available_slot {
                             next_available_slot = Some(index + 1);
@@ -112,10 +83,10 @@ impl Dish {
                         } else {
                             i
                         };
-                        d.content[row][j] = Some(Rock::Rounded);
+                        d.content[(row, j)] = Some(Rock::Rounded);
                     }
                     Some(Rock::Squared) => {
-                        d.content[i][j] = Some(Rock::Squared);
+                        d.content[(i, j)] = Some(Rock::Squared);
                         next_available_slot = None;
                     }
                     None => {
@@ -132,14 +103,13 @@ impl Dish {
 
     fn slide_west(&self) -> Dish {
         let mut d = Dish {
-            size: self.size,
-            content: vec![vec![None; self.size.1]; self.size.0],
+            content: Grid::new(self.content.num_rows(), self.content.num_cols(), None),
         };
 
-        for i in 0..self.size.0 {
+        for i in 0..self.content.num_rows() {
             let mut next_available_slot: Option<usize> = None;
-            for j in 0..self.size.1 {
-                match self.content[i][j] {
+            for j in 0..self.content.num_cols() {
+                match self.content[(i, j)] {
                     Some(Rock::Rounded) => {
                         let col = if let Some(index) = next_available_slot {
                             next_available_slot = Some(index + 1);
@@ -147,10 +117,10 @@ impl Dish {
                         } else {
                             j
                         };
-                        d.content[i][col] = Some(Rock::Rounded);
+                        d.content[(i, col)] = Some(Rock::Rounded);
                     }
                     Some(Rock::Squared) => {
-                        d.content[i][j] = Some(Rock::Squared);
+                        d.content[(i, j)] = Some(Rock::Squared);
                         next_available_slot = None;
                     }
                     None => {
@@ -167,30 +137,29 @@ impl Dish {
 
     fn slide_south(&self) -> Dish {
         let mut d = Dish {
-            size: self.size,
-            content: vec![vec![None; self.size.1]; self.size.0],
+            content: Grid::new(self.content.num_rows(), self.content.num_cols(), None),
         };
 
-        for j in 0..self.size.1 {
+        for j in 0..self.content.num_cols() {
             let mut next_available_slot: Option<usize> = None;
-            for i in 0..self.size.0 {
-                match self.content[self.size.0 - i - 1][j] {
+            for i in 0..self.content.num_rows() {
+                match self.content[(self.content.num_rows() - i - 1, j)] {
                     Some(Rock::Rounded) => {
                         let row = if let Some(index) = next_available_slot {
                             next_available_slot = Some(index - 1);
                             index
                         } else {
-                            self.size.0 - i - 1
+                            self.content.num_rows() - i - 1
                         };
-                        d.content[row][j] = Some(Rock::Rounded);
+                        d.content[(row, j)] = Some(Rock::Rounded);
                     }
                     Some(Rock::Squared) => {
-                        d.content[self.size.0 - i - 1][j] = Some(Rock::Squared);
+                        d.content[(self.content.num_rows() - i - 1, j)] = Some(Rock::Squared);
                         next_available_slot = None;
                     }
                     None => {
                         if next_available_slot.is_none() {
-                            next_available_slot = Some(self.size.0 - i - 1);
+                            next_available_slot = Some(self.content.num_rows() - i - 1);
                         }
                     }
                 }
@@ -202,30 +171,29 @@ impl Dish {
 
     fn slide_east(&self) -> Dish {
         let mut d = Dish {
-            size: self.size,
-            content: vec![vec![None; self.size.1]; self.size.0],
+            content: Grid::new(self.content.num_rows(), self.content.num_cols(), None),
         };
 
-        for i in 0..self.size.0 {
+        for i in 0..self.content.num_rows() {
             let mut next_available_slot: Option<usize> = None;
-            for j in 0..self.size.1 {
-                match self.content[i][self.size.1 - j - 1] {
+            for j in 0..self.content.num_cols() {
+                match self.content[(i, self.content.num_cols() - j - 1)] {
                     Some(Rock::Rounded) => {
                         let col = if let Some(index) = next_available_slot {
                             next_available_slot = Some(index - 1);
                             index
                         } else {
-                            self.size.1 - j - 1
+                            self.content.num_cols() - j - 1
                         };
-                        d.content[i][col] = Some(Rock::Rounded);
+                        d.content[(i, col)] = Some(Rock::Rounded);
                     }
                     Some(Rock::Squared) => {
-                        d.content[i][self.size.1 - j - 1] = Some(Rock::Squared);
+                        d.content[(i, self.content.num_cols() - j - 1)] = Some(Rock::Squared);
                         next_available_slot = None;
                     }
                     None => {
                         if next_available_slot.is_none() {
-                            next_available_slot = Some(self.size.1 - j - 1);
+                            next_available_slot = Some(self.content.num_cols() - j - 1);
                         }
                     }
                 }
@@ -253,7 +221,7 @@ impl Dish {
     pub fn load_after_cycles_fast(&self, n: u64) -> u64 {
         // this is periodical dynamic system
         // let's just hope the period is not too large
-        
+
         let mut to_next: HashMap<String, String> = HashMap::new();
         let mut to_step_and_load: HashMap<String, (u64, u64)> = HashMap::new();
 
@@ -261,7 +229,9 @@ impl Dish {
 
         let mut count = 1u64;
         let mut next = self.cycle();
-        to_step_and_load.insert(next.to_string(), (count, next.load()));
+        to_step_and_load
+            .entry(next.to_string())
+            .or_insert((count, next.load()));
         to_next.insert(self.to_string(), next.to_string());
 
         while count < n && !to_next.contains_key(&next.to_string()) {
@@ -271,7 +241,9 @@ impl Dish {
                 next = tmp;
                 break;
             }
-            to_step_and_load.insert(tmp.to_string(), (count, tmp.load()));
+            to_step_and_load
+                .entry(tmp.to_string())
+                .or_insert((count, tmp.load()));
             to_next.insert(next.to_string(), tmp.to_string());
 
             next = tmp;
@@ -297,7 +269,7 @@ impl Dish {
 
     pub fn load_after_one_slide_north_fast(&self) -> u64 {
         let mut load = 0u64;
-        for j in 0..self.size.1 {
+        for j in 0..self.content.num_cols() {
             load += self.column_load_after_slide_north(j);
         }
         load
@@ -306,8 +278,8 @@ impl Dish {
     fn column_load_after_slide_north(&self, column: usize) -> u64 {
         let mut load = 0usize;
         let mut next_available_slot: Option<usize> = None;
-        for i in 0..self.size.0 {
-            match self.content[i][column] {
+        for i in 0..self.content.num_rows() {
+            match self.content[(i, column)] {
                 Some(Rock::Rounded) => {
                     let row = if let Some(index) = next_available_slot {
                         next_available_slot = Some(index + 1);
@@ -315,7 +287,7 @@ impl Dish {
                     } else {
                         i
                     };
-                    load += self.size.0 - row;
+                    load += self.content.num_rows() - row;
                 }
                 Some(Rock::Squared) => next_available_slot = None,
                 None => {
@@ -332,7 +304,14 @@ impl Dish {
 
 impl fmt::Display for Dish {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.to_string())
+        write!(
+            f,
+            "{}",
+            self.content.render(|cell| match cell {
+                Some(rock) => rock.into(),
+                None => '.',
+            })
+        )
     }
 }
 
@@ -352,6 +331,13 @@ O.#..O.#.#
 #....###..
 #OO..#....";
 
+    #[test]
+    fn test_parse_error() {
+        let e = Dish::try_parse("O..#\nO.o.").err().unwrap();
+        assert_eq!((e.line(), e.column()), (2, 3));
+        assert_eq!(e.expected(), "'.', 'O' or '#'");
+    }
+
     #[test]
     fn test_column_load() {
         let d = Dish::parse(&INPUT);
@@ -430,6 +416,13 @@ O.#..O.#.#
         assert_eq!(d.load_after_cycles_fast(1_000_000_000), 64);
     }
 
+    #[test]
+    fn test_load_after_cycles_fast_fixed_point() {
+        let d = Dish::parse("#.\n.O");
+        assert_eq!(d.load_after_cycles_fast(1), 1);
+        assert_eq!(d.load_after_cycles_fast(1_000_000_000), 1);
+    }
+
     #[test]
     fn test_load_after_cycles_real_data() {
         let input = fs::read_to_string("data/day14.txt").unwrap();
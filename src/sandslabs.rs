@@ -1,5 +1,8 @@
+use crate::error::{parse_token, ParseError, ReadError};
+use crate::stream::parse_lines;
 use std::collections::HashSet;
 use std::collections::VecDeque;
+use std::io::BufRead;
 
 #[derive(Debug, PartialEq)]
 struct Point {
@@ -9,12 +12,24 @@ struct Point {
 }
 
 impl Point {
-    fn parse(input: &str) -> Point {
-        let mut coordinates = input.split(',').map(|s| s.parse::<usize>().unwrap());
-        let x = coordinates.next().unwrap();
-        let y = coordinates.next().unwrap();
-        let z = coordinates.next().unwrap();
-        Point { x, y, z }
+    fn try_parse(input: &str) -> Result<Point, ParseError> {
+        let mut coordinates = input.split(',');
+        let mut xyz = [0usize; 3];
+        for c in xyz.iter_mut() {
+            let str_c = coordinates
+                .next()
+                .ok_or_else(|| ParseError::at_end(input, "coordinate"))?;
+            *c = parse_token(input, str_c, "coordinate")?;
+        }
+        if coordinates.next().is_some() {
+            let extra = &input[input.match_indices(',').nth(2).unwrap().0..];
+            return Err(ParseError::at(input, extra, "'~' or end of line"));
+        }
+        Ok(Point {
+            x: xyz[0],
+            y: xyz[1],
+            z: xyz[2],
+        })
     }
 
     fn down_by(&mut self, height: usize) {
@@ -30,6 +45,26 @@ pub struct Brick {
 }
 
 impl Brick {
+    fn try_parse(line: &str) -> Result<Brick, ParseError> {
+        let tilde_index = line
+            .find('~')
+            .ok_or_else(|| ParseError::at_end(line, "'~'"))?;
+        let (str_start, str_end) = (&line[..tilde_index], &line[tilde_index + 1..]);
+        let start = Point::try_parse(str_start).map_err(|e| e.within(line, str_start))?;
+        let end = Point::try_parse(str_end).map_err(|e| e.within(line, str_end))?;
+        if end.x < start.x || end.y < start.y || end.z < start.z {
+            return Err(ParseError::at(
+                line,
+                str_end,
+                "coordinates not below the start",
+            ));
+        }
+        if start.z == 0 {
+            return Err(ParseError::at(line, str_start, "brick above the ground"));
+        }
+        Ok(Brick { start, end })
+    }
+
     fn fall_by(&mut self, height: usize) {
         self.start.down_by(height);
         self.end.down_by(height);
@@ -37,20 +72,29 @@ impl Brick {
 }
 
 pub fn read_bricks(input: &str) -> Vec<Brick> {
+    try_read_bricks(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_read_bricks(input: &str) -> Result<Vec<Brick>, ParseError> {
     let mut bricks = Vec::new();
 
     for line in input.lines() {
-        let tilde_index = line.find('~').unwrap();
-        bricks.push(Brick {
-            start: Point::parse(&line[..tilde_index]),
-            end: Point::parse(&line[tilde_index + 1..]),
-        });
+        bricks.push(Brick::try_parse(line).map_err(|e| e.within(input, line))?);
+    }
+    if bricks.is_empty() {
+        return Err(ParseError::at_end(input, "brick"));
     }
 
     bricks.sort_by_key(|k| k.start.z);
     fall(&mut bricks);
 
-    bricks
+    Ok(bricks)
+}
+
+/// Lazily reads a snapshot of bricks, one per line, as they are before they
+/// fall.
+pub fn stream_bricks<R: BufRead>(reader: R) -> impl Iterator<Item = Result<Brick, ReadError>> {
+    parse_lines(reader, Brick::try_parse)
 }
 
 // assume that min_x and min_y are 0
@@ -63,8 +107,8 @@ fn xy_coverage(bricks: &[Brick]) -> (usize, usize) {
         if b.end.x > max_x {
             max_x = b.end.x;
         }
-        if b.end.x > max_y {
-            max_y = b.end.x;
+        if b.end.y > max_y {
+            max_y = b.end.y;
         }
     }
     (max_x + 1, max_y + 1)
@@ -238,6 +282,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_read_bricks_errors() {
+        let e = try_read_bricks("1,0,1~1,2,1\n0,1,6-2,1,6").unwrap_err();
+        assert_eq!((e.line(), e.column()), (2, 12));
+
+        let e = try_read_bricks("1,0,1~1,2\n0,1,6~2,1,6").unwrap_err();
+        assert_eq!((e.line(), e.column()), (1, 10));
+        assert_eq!(e.expected(), "coordinate");
+
+        let e = try_read_bricks("1,0,1~1,2,1,4").unwrap_err();
+        assert_eq!((e.line(), e.column()), (1, 12));
+    }
+
+    #[test]
+    fn test_read_bricks_deeper_than_wide() {
+        let bricks = read_bricks("0,1,3~0,2,3\n0,1,1~0,1,1");
+        assert_eq!(bricks[1].start.z, 2);
+        assert_eq!(count_disintegrable_bricks(bricks.as_slice()), 1);
+    }
+
+    #[test]
+    fn test_stream_bricks() {
+        let bricks: Vec<Brick> = stream_bricks(INPUT.as_bytes())
+            .map(|b| b.unwrap())
+            .collect();
+        assert_eq!(bricks.len(), 7);
+        assert_eq!(
+            bricks[6],
+            Brick {
+                start: Point { x: 1, y: 1, z: 8 },
+                end: Point { x: 1, y: 1, z: 9 }
+            }
+        );
+
+        match stream_bricks("1,0,1~1,2,1\n0,1,6-2,1,6".as_bytes()).nth(1) {
+            Some(Err(ReadError::Parse(e))) => assert_eq!((e.line(), e.column()), (2, 12)),
+            _ => panic!("expected a parse error"),
+        }
+    }
+
     #[test]
     fn test_count_disintegrable() {
         let bricks = read_bricks(&INPUT);
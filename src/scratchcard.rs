@@ -1,5 +1,12 @@
-use std::collections::BTreeSet;
+use crate::error::{parse_token, ParseError, ReadError};
+use crate::stream::parse_lines;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::io::BufRead;
+use std::ops::Range;
 
+#[derive(Clone)]
 pub struct Card {
     id: usize,
     winning: Vec<u32>,
@@ -8,33 +15,54 @@ pub struct Card {
 
 impl Card {
     pub fn parse(input: &str) -> Card {
-        let first_digit_index = input.find(|c: char| c.is_digit(10)).unwrap();
-        let column_index = input.find(":").unwrap();
+        Card::try_parse(input).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_parse(input: &str) -> Result<Card, ParseError> {
+        let header = input
+            .strip_prefix("Card")
+            .ok_or_else(|| ParseError::at(input, &input[..input.len().min(4)], "'Card'"))?;
+        let column_index = input
+            .find(':')
+            .ok_or_else(|| ParseError::at_end(input, "':'"))?;
         let content = &input[column_index + 1..];
-        let separator_index = content.find('|').unwrap();
+        let separator_index = content
+            .find('|')
+            .ok_or_else(|| ParseError::at_end(input, "'|'"))?;
         let str_winning = &content[..separator_index];
         let str_numbers = &content[separator_index + 1..];
 
         let mut c = Card {
-            id: input[first_digit_index..column_index].parse().unwrap(),
+            id: parse_token(input, header[..column_index - 4].trim(), "card id")?,
             winning: Vec::new(),
             numbers: BTreeSet::new(),
         };
-        c.winning.extend(
-            str_winning
-                .split_whitespace()
-                .filter_map(|s| s.parse::<u32>().ok()),
-        );
-        c.numbers.extend(
-            str_numbers
-                .split_whitespace()
-                .filter_map(|s| s.parse::<u32>().ok()),
-        );
+        for str_num in str_winning.split_whitespace() {
+            c.winning.push(parse_token(input, str_num, "number")?);
+        }
+        for str_num in str_numbers.split_whitespace() {
+            c.numbers.insert(parse_token(input, str_num, "number")?);
+        }
+
+        Ok(c)
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Winning numbers, in the order of the card.
+    pub fn winning(&self) -> &[u32] {
+        &self.winning
+    }
 
-        c
+    /// Numbers held, in increasing order.
+    pub fn held(&self) -> impl Iterator<Item = u32> + '_ {
+        self.numbers.iter().copied()
     }
 
-    fn num_wins(&self) -> usize {
+    /// Returns the number of winning numbers held.
+    pub fn num_matches(&self) -> usize {
         self.winning
             .iter()
             .filter(|w| self.numbers.contains(w))
@@ -42,29 +70,321 @@ impl Card {
     }
 
     pub fn value(&self) -> u32 {
-        match self.num_wins() {
-            0 => 0,
-            i => 1 << (i - 1),
-        }
+        self.value_with(&ScoringRule::standard())
+    }
+
+    pub fn value_with(&self, rule: &ScoringRule) -> u32 {
+        (rule.points)(self)
+    }
+}
+
+/// How a card is scored: the points it is worth and the follow-up cards it
+/// wins.
+///
+/// Follow-up cards are given as positions among the cards following the
+/// card in the order of ids, `0` being the next card.
+pub struct ScoringRule {
+    points: fn(&Card) -> u32,
+    awards: fn(&Card) -> Range<usize>,
+}
+
+impl ScoringRule {
+    pub fn new(points: fn(&Card) -> u32, awards: fn(&Card) -> Range<usize>) -> ScoringRule {
+        ScoringRule { points, awards }
+    }
+
+    /// The rules of the puzzle: one point for the first match, doubled for
+    /// each other match, and one copy of each of the next cards per match.
+    pub fn standard() -> ScoringRule {
+        ScoringRule::new(
+            |card| match card.num_matches() {
+                0 => 0,
+                i => 1 << (i - 1),
+            },
+            |card| 0..card.num_matches(),
+        )
     }
 }
 
-pub fn num_cards(cards: impl Iterator<Item = Card>) -> u32 {
-    let mut card_numbers: Vec<u32> = Vec::new();
-    for card in cards {
-        if card_numbers.len() < card.id {
-            card_numbers.push(0);
+pub fn try_read_cards(input: &str) -> Result<Vec<Card>, ParseError> {
+    input
+        .lines()
+        .map(|line| Card::try_parse(line).map_err(|e| e.within(input, line)))
+        .collect()
+}
+
+/// Lazily reads cards, one per line.
+pub fn stream_cards<R: BufRead>(reader: R) -> impl Iterator<Item = Result<Card, ReadError>> {
+    parse_lines(reader, Card::try_parse)
+}
+
+/// Returns the total number of cards, originals and copies, at the end of
+/// the cascade of `cards`.
+///
+/// The total is a `u64`, as cascades easily outgrow a `u32`. Panics if card
+/// ids are repeated or if the total overflows; see `Cascade` for a fallible
+/// version.
+pub fn num_cards(cards: impl Iterator<Item = Card>) -> u64 {
+    num_cards_with(cards, &ScoringRule::standard())
+}
+
+pub fn num_cards_with(cards: impl Iterator<Item = Card>, rule: &ScoringRule) -> u64 {
+    Cascade::with_rule(cards, rule).total()
+}
+
+/// Why cards cannot be cascaded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CascadeError {
+    DuplicateId(usize),
+    /// More cards than a `u64` can count.
+    Overflow,
+}
+
+impl fmt::Display for CascadeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CascadeError::DuplicateId(id) => write!(f, "card {} appears more than once", id),
+            CascadeError::Overflow => write!(f, "more than {} cards", u64::MAX),
         }
-        card_numbers[card.id - 1] += 1;
+    }
+}
+
+impl CascadeError {
+    /// Locates the error in `input`, the text the cards were read from: a
+    /// repeated id at its second occurrence, an overflow at the end.
+    pub fn within(&self, input: &str) -> ParseError {
+        let CascadeError::DuplicateId(id) = self else {
+            return ParseError::at_end(input, "fewer winning cards");
+        };
+        let line = input
+            .lines()
+            .filter(|line| Card::try_parse(line).is_ok_and(|card| card.id == *id))
+            .nth(1)
+            .unwrap_or(&input[input.len()..]);
+        let token = match line.find(':') {
+            Some(column_index) => line[..column_index].trim_start_matches("Card").trim(),
+            None => line,
+        };
+        ParseError::at(line, token, "a new card id").within(input, line)
+    }
+}
+
+impl Error for CascadeError {}
+
+/// Numbers of copies of each card, original included, once every card won
+/// has been scratched.
+///
+/// Each copy of a card wins a copy of the cards awarded by the scoring rule,
+/// counted among the cards following it in the order of ids, whatever the
+/// order in which cards are given. Missing ids are skipped, and wins past
+/// the last card are lost. Each id must appear once.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cascade {
+    copies: BTreeMap<usize, u64>,
+    total: u64,
+}
+
+impl Cascade {
+    pub fn new(cards: impl IntoIterator<Item = Card>) -> Cascade {
+        Cascade::try_new(cards).unwrap_or_else(|e| panic!("{}", e))
+    }
 
-        let num_wins = card.num_wins();
-        while card_numbers.len() < card.id + num_wins {
-            card_numbers.push(0);
+    pub fn try_new(cards: impl IntoIterator<Item = Card>) -> Result<Cascade, CascadeError> {
+        Cascade::try_with_rule(cards, &ScoringRule::standard())
+    }
+
+    pub fn with_rule(cards: impl IntoIterator<Item = Card>, rule: &ScoringRule) -> Cascade {
+        Cascade::try_with_rule(cards, rule).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_with_rule(
+        cards: impl IntoIterator<Item = Card>,
+        rule: &ScoringRule,
+    ) -> Result<Cascade, CascadeError> {
+        let mut awards: BTreeMap<usize, Range<usize>> = BTreeMap::new();
+        for card in cards {
+            if awards.insert(card.id, (rule.awards)(&card)).is_some() {
+                return Err(CascadeError::DuplicateId(card.id));
+            }
         }
-        for i in 1..=card.num_wins() {
-            card_numbers[card.id - 1 + i] += card_numbers[card.id - 1];
+
+        let mut copies: Vec<(usize, u64)> = awards.keys().map(|id| (*id, 1)).collect();
+        let mut total: u64 = 0;
+        for (i, awarded) in awards.values().enumerate() {
+            let won = copies[i].1;
+            total = total.checked_add(won).ok_or(CascadeError::Overflow)?;
+            let following = &mut copies[i + 1..];
+            let end = awarded.end.min(following.len());
+            for card in following[awarded.start.min(end)..end].iter_mut() {
+                card.1 = card.1.checked_add(won).ok_or(CascadeError::Overflow)?;
+            }
         }
+
+        Ok(Cascade {
+            copies: copies.into_iter().collect(),
+            total,
+        })
+    }
+
+    /// Returns the number of copies of card `id`, or `None` if there is no
+    /// such card.
+    pub fn copies(&self, id: usize) -> Option<u64> {
+        self.copies.get(&id).copied()
+    }
+
+    /// Iterates over the ids of the cards, in increasing order, with their
+    /// numbers of copies.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, u64)> + '_ {
+        self.copies.iter().map(|(id, copies)| (*id, *copies))
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
+
+    #[test]
+    fn test_value() {
+        let cards = try_read_cards(INPUT).unwrap();
+        let values: Vec<u32> = cards.iter().map(|c| c.value()).collect();
+        assert_eq!(values, vec![8, 2, 2, 1, 0, 0]);
+    }
+
+    #[test]
+    fn test_accessors() {
+        let card = Card::parse(&INPUT[..INPUT.find('\n').unwrap()]);
+        assert_eq!(card.id(), 1);
+        assert_eq!(card.winning(), &[41, 48, 83, 86, 17]);
+        assert_eq!(
+            card.held().collect::<Vec<u32>>(),
+            vec![6, 9, 17, 31, 48, 53, 83, 86]
+        );
+        assert_eq!(card.num_matches(), 4);
+    }
+
+    #[test]
+    fn test_value_with() {
+        let linear = ScoringRule::new(|card| card.num_matches() as u32, |_| 0..0);
+        let cards = try_read_cards(INPUT).unwrap();
+        let values: Vec<u32> = cards.iter().map(|c| c.value_with(&linear)).collect();
+        assert_eq!(values, vec![4, 2, 2, 1, 0, 0]);
     }
 
-    card_numbers.iter().sum()
+    #[test]
+    fn test_num_cards_with() {
+        let cards = || try_read_cards(INPUT).unwrap().into_iter();
+        let nothing = ScoringRule::new(|_| 0, |_| 0..0);
+        assert_eq!(num_cards_with(cards(), &nothing), 6);
+        // every card wins the one after next, whatever its matches
+        let skip = ScoringRule::new(|_| 0, |_| 1..2);
+        assert_eq!(
+            Cascade::with_rule(cards(), &skip)
+                .iter()
+                .collect::<Vec<(usize, u64)>>(),
+            vec![(1, 1), (2, 1), (3, 2), (4, 2), (5, 3), (6, 3)]
+        );
+    }
+
+    #[test]
+    fn test_num_cards() {
+        let cards = try_read_cards(INPUT).unwrap();
+        assert_eq!(num_cards(cards.into_iter()), 30);
+    }
+
+    #[test]
+    fn test_cascade() {
+        let cascade = Cascade::new(try_read_cards(INPUT).unwrap());
+        assert_eq!(
+            cascade.iter().collect::<Vec<(usize, u64)>>(),
+            vec![(1, 1), (2, 2), (3, 4), (4, 8), (5, 14), (6, 1)]
+        );
+        assert_eq!(cascade.total(), 30);
+        assert_eq!(cascade.copies(7), None);
+    }
+
+    #[test]
+    fn test_cascade_out_of_order() {
+        let mut cards = try_read_cards(INPUT).unwrap();
+        cards.reverse();
+        cards.swap(1, 4);
+        assert_eq!(Cascade::new(cards).total(), 30);
+    }
+
+    #[test]
+    fn test_cascade_gaps_and_caps() {
+        // card 10 has 2 matches and wins cards 20 and 30, the only ones left;
+        // card 30 has 3 matches but is the last card
+        let cards = try_read_cards(
+            "Card 30: 1 2 3 | 1 2 3\n\
+            Card 10: 1 2 | 1 2 9\n\
+            Card 20: 5 | 6",
+        )
+        .unwrap();
+        let cascade = Cascade::new(cards);
+        assert_eq!(
+            cascade.iter().collect::<Vec<(usize, u64)>>(),
+            vec![(10, 1), (20, 2), (30, 2)]
+        );
+        assert_eq!(cascade.total(), 5);
+    }
+
+    #[test]
+    fn test_cascade_errors() {
+        let cards = || try_read_cards(INPUT).unwrap();
+
+        let mut duplicated = cards();
+        duplicated.push(cards()[2].clone());
+        let e = Cascade::try_new(duplicated).unwrap_err();
+        assert_eq!(e, CascadeError::DuplicateId(3));
+        assert_eq!(e.to_string(), "card 3 appears more than once");
+        let input = format!("{}\nCard 3: 1 | 1", INPUT);
+        let e = e.within(&input);
+        assert_eq!((e.line(), e.column()), (7, 6));
+        assert_eq!(e.expected(), "a new card id");
+
+        // each of 70 cards wins all the following ones, doubling the copies
+        // of every next card
+        let all = ScoringRule::new(|_| 0, |_| 0..usize::MAX);
+        let many: Vec<Card> = (1..=70)
+            .map(|id| Card::parse(&format!("Card {}: 1 | 1", id)))
+            .collect();
+        let e = Cascade::try_with_rule(many.clone(), &all).unwrap_err();
+        assert_eq!(e, CascadeError::Overflow);
+        assert_eq!(
+            Cascade::try_with_rule(many[..64].to_vec(), &all)
+                .unwrap()
+                .total(),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn test_stream_cards() {
+        let cards = stream_cards(INPUT.as_bytes()).map(|c| c.unwrap());
+        assert_eq!(num_cards(cards), 30);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let e = try_read_cards("Card 1: 41 48 | 83 86\nCard 2: 13 x2 | 61")
+            .err()
+            .unwrap();
+        assert_eq!((e.line(), e.column()), (2, 12));
+        assert_eq!(e.expected(), "number");
+
+        let e = Card::try_parse("Card 1: 41 48 83 86").err().unwrap();
+        assert_eq!(e.expected(), "'|'");
+        assert_eq!(e.column(), 20);
+    }
 }
@@ -1,3 +1,4 @@
+use crate::error::ParseError;
 use std::collections::HashMap;
 
 mod graph {
@@ -5,6 +6,7 @@ mod graph {
     use std::collections::HashMap;
     use std::collections::HashSet;
 
+    #[derive(Clone)]
     pub struct Contractable {
         vertices: Vec<u32>,
         edges: Vec<HashMap<usize, u32>>,
@@ -115,7 +117,6 @@ mod graph {
         while g.num_vertices() > 1 {
             let v = g.pick_vertex();
             let (cut, x, y) = min_cut_from_seed(g, v);
-            println!("from {}, cut at {} <-> {}, with cost {}", v, x, y, cut.size);
             g.contract(x, y);
 
             if cut.size == size {
@@ -292,14 +293,29 @@ mod graph {
     }
 }
 
+pub use graph::Contractable;
+
 pub fn read_graph(input: &str) -> graph::Contractable {
+    try_read_graph(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_read_graph(input: &str) -> Result<graph::Contractable, ParseError> {
     let mut g = graph::Contractable::new();
 
     let mut vertices: HashMap<String, usize> = HashMap::new();
     for line in input.lines() {
-        let column_index = line.find(':').unwrap();
+        let column_index = line
+            .find(':')
+            .ok_or_else(|| ParseError::at(input, &line[line.len()..], "':'"))?;
+        if line[..column_index].trim().is_empty() {
+            return Err(ParseError::at(
+                input,
+                &line[..column_index],
+                "component name",
+            ));
+        }
 
-        let str_v = String::from(&line[..column_index]);
+        let str_v = String::from(line[..column_index].trim());
         let v = if let Some(i) = vertices.get(&str_v) {
             *i
         } else {
@@ -310,7 +326,7 @@ pub fn read_graph(input: &str) -> graph::Contractable {
 
         for str_neighbour in line[column_index + 1..]
             .split_whitespace()
-            .map(|s| String::from(s))
+            .map(String::from)
         {
             let neighbour = if let Some(i) = vertices.get(&str_neighbour) {
                 *i
@@ -323,7 +339,7 @@ pub fn read_graph(input: &str) -> graph::Contractable {
         }
     }
 
-    g
+    Ok(g)
 }
 
 pub fn cut_product_size(g: &mut graph::Contractable) -> u32 {
@@ -355,6 +371,15 @@ frs: qnr lhk lsr";
         assert_eq!(g.num_vertices(), 15);
     }
 
+    #[test]
+    fn test_read_graph_error() {
+        let e = try_read_graph("jqt: rhn xhk nvd\nrsh frs pzl lsr")
+            .err()
+            .unwrap();
+        assert_eq!((e.line(), e.column()), (2, 16));
+        assert_eq!(e.expected(), "':'");
+    }
+
     #[test]
     fn test_cut() {
         let mut g = read_graph(&INPUT);
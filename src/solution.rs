@@ -0,0 +1,665 @@
+use crate::almanac::{min_location_ex1, min_location_ex2, Almanac};
+use crate::beam_tracer::Contraption;
+use crate::calibration;
+use crate::camelcard;
+use crate::camelcard2;
+use crate::cube_game::{sum_min_cubes_powers_of, sum_possible_game_ids, try_read_games, Bag, Game};
+use crate::desert;
+use crate::engine_schematic::EngineSchematic;
+use crate::error::ParseError;
+use crate::hash::{focusing_power_of, sum_hash_of, try_read_steps, Step};
+use crate::heatloss::LossMap;
+use crate::hotsprings::{try_read_records, ConditionRecord};
+use crate::lavaduct::{
+    dig, dug_volume, try_read_dig_plan, try_read_dig_plan_correctly, Instruction,
+};
+use crate::oasis::{
+    sum_extrapolated_next_values_of, sum_extrapolated_previous_values_of, try_read_series,
+};
+use crate::observatory::SpaceMap;
+use crate::pipe::{count_enclosed_tiles, loop_size, TileMap};
+use crate::race::{num_possibilities_of, prod_num_possibilities_of, try_read_race, try_read_races};
+use crate::reflection::{try_read_patterns, Pattern};
+use crate::rocks::Dish;
+use crate::sandslabs::{count_disintegrable_bricks, sum_chain_reactions, try_read_bricks, Brick};
+use crate::scratchcard::{try_read_cards, Card, Cascade};
+use crate::snowverload::{cut_product_size, try_read_graph, Contractable};
+use std::collections::HashMap;
+
+/// The solution of one day: the puzzle input is parsed once, then both parts
+/// are answered from the parsed input.
+///
+/// A part returns `None` when it is not solved.
+pub trait Solution {
+    fn parse(input: &str) -> Result<Self, ParseError>
+    where
+        Self: Sized;
+
+    fn part1(&self) -> Option<String>;
+
+    fn part2(&self) -> Option<String>;
+}
+
+/// A registered day, mapping a day number to its solution.
+pub struct Day {
+    number: u32,
+    name: &'static str,
+    parse: fn(&str) -> Result<Box<dyn Solution>, ParseError>,
+    slow: &'static [u32],
+}
+
+impl Day {
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Returns the name of the module solving this day.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Box<dyn Solution>, ParseError> {
+        (self.parse)(input)
+    }
+
+    /// Tells whether solving `part` on the puzzle input takes minutes or
+    /// more. Slow parts are skipped when every day is run.
+    pub fn is_slow(&self, part: u32) -> bool {
+        self.slow.contains(&part)
+    }
+}
+
+fn boxed<S: Solution + 'static>(input: &str) -> Result<Box<dyn Solution>, ParseError> {
+    Ok(Box::new(S::parse(input)?))
+}
+
+const DAYS: [Day; 20] = [
+    Day {
+        number: 1,
+        name: "calibration",
+        parse: boxed::<Day1>,
+        slow: &[],
+    },
+    Day {
+        number: 2,
+        name: "cube_game",
+        parse: boxed::<Day2>,
+        slow: &[],
+    },
+    Day {
+        number: 3,
+        name: "engine_schematic",
+        parse: boxed::<EngineSchematic>,
+        slow: &[],
+    },
+    Day {
+        number: 4,
+        name: "scratchcard",
+        parse: boxed::<Day4>,
+        slow: &[],
+    },
+    Day {
+        number: 5,
+        name: "almanac",
+        parse: boxed::<Almanac>,
+        slow: &[],
+    },
+    Day {
+        number: 6,
+        name: "race",
+        parse: boxed::<Day6>,
+        slow: &[],
+    },
+    Day {
+        number: 7,
+        name: "camelcard",
+        parse: boxed::<Day7>,
+        slow: &[],
+    },
+    Day {
+        number: 8,
+        name: "desert",
+        parse: boxed::<Day8>,
+        slow: &[],
+    },
+    Day {
+        number: 9,
+        name: "oasis",
+        parse: boxed::<Day9>,
+        slow: &[],
+    },
+    Day {
+        number: 10,
+        name: "pipe",
+        parse: boxed::<TileMap>,
+        slow: &[],
+    },
+    Day {
+        number: 11,
+        name: "observatory",
+        parse: boxed::<SpaceMap>,
+        slow: &[],
+    },
+    Day {
+        number: 12,
+        name: "hotsprings",
+        parse: boxed::<Day12>,
+        slow: &[2],
+    },
+    Day {
+        number: 13,
+        name: "reflection",
+        parse: boxed::<Day13>,
+        slow: &[],
+    },
+    Day {
+        number: 14,
+        name: "rocks",
+        parse: boxed::<Dish>,
+        slow: &[],
+    },
+    Day {
+        number: 15,
+        name: "hash",
+        parse: boxed::<Day15>,
+        slow: &[],
+    },
+    Day {
+        number: 16,
+        name: "beam_tracer",
+        parse: boxed::<Contraption>,
+        slow: &[],
+    },
+    Day {
+        number: 17,
+        name: "heatloss",
+        parse: boxed::<LossMap>,
+        slow: &[1],
+    },
+    Day {
+        number: 18,
+        name: "lavaduct",
+        parse: boxed::<Day18>,
+        slow: &[],
+    },
+    Day {
+        number: 22,
+        name: "sandslabs",
+        parse: boxed::<Day22>,
+        slow: &[],
+    },
+    Day {
+        number: 25,
+        name: "snowverload",
+        parse: boxed::<Day25>,
+        slow: &[1],
+    },
+];
+
+/// Returns every registered day, sorted by day number.
+pub fn days() -> &'static [Day] {
+    &DAYS
+}
+
+pub fn day(number: u32) -> Option<&'static Day> {
+    DAYS.iter().find(|d| d.number == number)
+}
+
+pub struct Day1 {
+    document: String,
+}
+
+impl Solution for Day1 {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        Ok(Day1 {
+            document: input.to_string(),
+        })
+    }
+
+    fn part1(&self) -> Option<String> {
+        None
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(calibration::sum(&self.document).to_string())
+    }
+}
+
+pub struct Day2 {
+    games: Vec<Game>,
+}
+
+impl Solution for Day2 {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        Ok(Day2 {
+            games: try_read_games(input)?,
+        })
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(sum_possible_game_ids(&self.games, &Bag::standard()).to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(sum_min_cubes_powers_of(&self.games, &Bag::standard()).to_string())
+    }
+}
+
+impl Solution for EngineSchematic {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        EngineSchematic::try_parse(input)
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(self.part_numbers().sum::<u32>().to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(self.gear_ratios().sum::<u32>().to_string())
+    }
+}
+
+pub struct Day4 {
+    cards: Vec<Card>,
+    cascade: Cascade,
+}
+
+impl Solution for Day4 {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        let cards = try_read_cards(input)?;
+        let cascade = Cascade::try_new(cards.iter().cloned()).map_err(|e| e.within(input))?;
+        Ok(Day4 { cards, cascade })
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(
+            self.cards
+                .iter()
+                .map(|c| c.value())
+                .sum::<u32>()
+                .to_string(),
+        )
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(self.cascade.total().to_string())
+    }
+}
+
+impl Solution for Almanac {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        Almanac::try_parse(input)
+    }
+
+    fn part1(&self) -> Option<String> {
+        min_location_ex1(self).map(|location| location.to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        min_location_ex2(self).map(|location| location.to_string())
+    }
+}
+
+pub struct Day6 {
+    races: Vec<(u64, u64)>,
+    race: (u64, u64),
+}
+
+impl Solution for Day6 {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        Ok(Day6 {
+            races: try_read_races(input)?,
+            race: try_read_race(input)?,
+        })
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(prod_num_possibilities_of(&self.races).to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(num_possibilities_of(self.race).to_string())
+    }
+}
+
+pub struct Day7 {
+    bids: Vec<(camelcard::Hand, u64)>,
+    bids2: Vec<(camelcard2::Hand, u64)>,
+}
+
+impl Solution for Day7 {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        Ok(Day7 {
+            bids: camelcard::try_read_bids(input)?,
+            bids2: camelcard2::try_read_bids2(input)?,
+        })
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(camelcard::total_winnings(&mut self.bids.clone()).to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(camelcard2::total_winnings2(&mut self.bids2.clone()).to_string())
+    }
+}
+
+pub struct Day8 {
+    instructions: Vec<desert::Direction>,
+    map: HashMap<String, (String, String)>,
+    a_nodes: Vec<String>,
+}
+
+impl Solution for Day8 {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        let (instructions, map, a_nodes) = desert::try_read_map(input)?;
+        Ok(Day8 {
+            instructions,
+            map,
+            a_nodes,
+        })
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(desert::count_steps_from_aaa_to_zzz(&self.instructions, &self.map).to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(
+            desert::count_steps_from_a_to_z(&self.instructions, &self.map, &self.a_nodes)
+                .to_string(),
+        )
+    }
+}
+
+pub struct Day9 {
+    series: Vec<Vec<i64>>,
+}
+
+impl Solution for Day9 {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        Ok(Day9 {
+            series: try_read_series(input)?,
+        })
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(sum_extrapolated_next_values_of(&self.series).to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(sum_extrapolated_previous_values_of(&self.series).to_string())
+    }
+}
+
+impl Solution for TileMap {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        TileMap::try_parse(input)
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some((loop_size(self) / 2).to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(count_enclosed_tiles(self).0.to_string())
+    }
+}
+
+impl Solution for SpaceMap {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        SpaceMap::try_parse(input)
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(self.sum_galaxy_pair_distances(2).to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(self.sum_galaxy_pair_distances(1_000_000).to_string())
+    }
+}
+
+pub struct Day12 {
+    records: Vec<ConditionRecord>,
+}
+
+impl Solution for Day12 {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        Ok(Day12 {
+            records: try_read_records(input)?,
+        })
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(
+            self.records
+                .iter()
+                .map(|r| r.count_arrangements())
+                .sum::<u64>()
+                .to_string(),
+        )
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(
+            self.records
+                .iter()
+                .map(|r| r.unfold(5).count_arrangements())
+                .sum::<u64>()
+                .to_string(),
+        )
+    }
+}
+
+pub struct Day13 {
+    patterns: Vec<Pattern>,
+}
+
+impl Solution for Day13 {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        Ok(Day13 {
+            patterns: try_read_patterns(input)?,
+        })
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(
+            self.patterns
+                .iter()
+                .map(Pattern::summary)
+                .sum::<u64>()
+                .to_string(),
+        )
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(
+            self.patterns
+                .iter()
+                .map(Pattern::summary_with_smudge)
+                .sum::<u64>()
+                .to_string(),
+        )
+    }
+}
+
+impl Solution for Dish {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        Dish::try_parse(input)
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(self.load_after_one_slide_north_fast().to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(self.load_after_cycles_fast(1_000_000_000).to_string())
+    }
+}
+
+pub struct Day15 {
+    /// The steps as written, which part 1 hashes.
+    texts: Vec<String>,
+    steps: Vec<Step>,
+}
+
+impl Solution for Day15 {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        Ok(Day15 {
+            steps: try_read_steps(input)?,
+            texts: input.trim_end().split(',').map(String::from).collect(),
+        })
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(sum_hash_of(self.texts.iter().map(String::as_str)).to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(focusing_power_of(&self.steps).to_string())
+    }
+}
+
+impl Solution for Contraption {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        Contraption::try_parse(input)
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(self.trace_beam().num_energized().to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(self.max_energized().to_string())
+    }
+}
+
+impl Solution for LossMap {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        LossMap::try_parse(input)
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(self.shortest_path().to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        None
+    }
+}
+
+pub struct Day18 {
+    plan: Vec<Instruction>,
+    correct_plan: Vec<Instruction>,
+}
+
+impl Solution for Day18 {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        Ok(Day18 {
+            plan: try_read_dig_plan(input)?,
+            correct_plan: try_read_dig_plan_correctly(input)?,
+        })
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(dig(&self.plan).volume().to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(dug_volume(&self.correct_plan).to_string())
+    }
+}
+
+pub struct Day22 {
+    bricks: Vec<Brick>,
+}
+
+impl Solution for Day22 {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        Ok(Day22 {
+            bricks: try_read_bricks(input)?,
+        })
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(count_disintegrable_bricks(&self.bricks).to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        Some(sum_chain_reactions(&self.bricks).to_string())
+    }
+}
+
+pub struct Day25 {
+    graph: Contractable,
+}
+
+impl Solution for Day25 {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        Ok(Day25 {
+            graph: try_read_graph(input)?,
+        })
+    }
+
+    fn part1(&self) -> Option<String> {
+        Some(cut_product_size(&mut self.graph.clone()).to_string())
+    }
+
+    fn part2(&self) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error_through_registry() {
+        let e = day(6)
+            .unwrap()
+            .parse("Time:      7  15   30\n")
+            .err()
+            .unwrap();
+        assert_eq!(e.expected(), "'Distance:'");
+    }
+
+    #[test]
+    fn test_days_are_sorted() {
+        let numbers: Vec<u32> = days().iter().map(|d| d.number()).collect();
+        let mut sorted = numbers.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(numbers, sorted);
+    }
+
+    #[test]
+    fn test_day() {
+        assert_eq!(day(16).unwrap().name(), "beam_tracer");
+        assert!(day(19).is_none());
+    }
+
+    #[test]
+    fn test_slow_parts() {
+        assert!(day(12).unwrap().is_slow(2));
+        assert!(!day(12).unwrap().is_slow(1));
+        assert!(day(17).unwrap().is_slow(1));
+        assert!(day(25).unwrap().is_slow(1));
+        let num_slow = days()
+            .iter()
+            .filter(|d| d.is_slow(1) || d.is_slow(2))
+            .count();
+        assert_eq!(num_slow, 3);
+    }
+
+    #[test]
+    fn test_solve_through_registry() {
+        let solution = day(6)
+            .unwrap()
+            .parse("Time:      7  15   30\nDistance:  9  40  200")
+            .unwrap();
+        assert_eq!(solution.part1(), Some("288".to_string()));
+        assert_eq!(solution.part2(), Some("71503".to_string()));
+    }
+}
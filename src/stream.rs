@@ -0,0 +1,115 @@
+//! Lazy parsing of line-oriented inputs read from a stream.
+
+use crate::error::{ParseError, ReadError};
+use std::io::BufRead;
+
+/// Iterator over the items of an input with one item per line.
+///
+/// Lines are read one at a time, so that the whole input never has to be in
+/// memory. Both `\n` and `\r\n` line endings are accepted. Parse errors are
+/// reported at their line in the input, and do not stop the iteration; it
+/// stops after an I/O error.
+pub struct LineParser<R, F> {
+    reader: R,
+    parse: F,
+    buffer: String,
+    line: usize,
+    done: bool,
+}
+
+/// Returns an iterator parsing each line of `reader` with `parse`.
+pub fn parse_lines<R, T, F>(reader: R, parse: F) -> LineParser<R, F>
+where
+    R: BufRead,
+    F: FnMut(&str) -> Result<T, ParseError>,
+{
+    LineParser {
+        reader,
+        parse,
+        buffer: String::new(),
+        line: 0,
+        done: false,
+    }
+}
+
+impl<R, T, F> Iterator for LineParser<R, F>
+where
+    R: BufRead,
+    F: FnMut(&str) -> Result<T, ParseError>,
+{
+    type Item = Result<T, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        self.buffer.clear();
+        match self.reader.read_line(&mut self.buffer) {
+            Ok(0) => {
+                self.done = true;
+                None
+            }
+            Ok(_) => {
+                self.line += 1;
+                let terminated = self.buffer.ends_with('\n');
+                let line = self.buffer.strip_suffix('\n').unwrap_or(&self.buffer);
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                let (number, parse) = (self.line, &mut self.parse);
+                Some(parse(line).map_err(|e| e.on_line(number, terminated).into()))
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e.into()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{parse_token, Found};
+    use std::io::{self, Read};
+
+    fn parse_number(line: &str) -> Result<u32, ParseError> {
+        parse_token(line, line, "number")
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let numbers: Vec<u32> = parse_lines("12\r\n13\n\n14".as_bytes(), parse_number)
+            .filter_map(|n| n.ok())
+            .collect();
+        assert_eq!(numbers, vec![12, 13, 14]);
+    }
+
+    #[test]
+    fn test_parse_lines_errors() {
+        let results: Vec<Result<u32, ReadError>> =
+            parse_lines("12\n1x\n14\n".as_bytes(), parse_number).collect();
+        assert_eq!(results.len(), 3);
+        match &results[1] {
+            Err(ReadError::Parse(e)) => {
+                assert_eq!((e.line(), e.column()), (2, 1));
+                assert_eq!(e.found(), &Found::Token("1x".to_string()));
+            }
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    struct Broken;
+
+    impl Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("broken"))
+        }
+    }
+
+    #[test]
+    fn test_parse_lines_io_error() {
+        let mut lines = parse_lines(io::BufReader::new(Broken), parse_number);
+        assert!(matches!(lines.next(), Some(Err(ReadError::Io(_)))));
+        assert!(lines.next().is_none());
+    }
+}
@@ -7,12 +7,12 @@ use std::fs;
 fn day_5_1() {
     let input = fs::read_to_string("data/day5.txt").unwrap();
     let min = min_location_ex1(&Almanac::parse(&input));
-    assert_eq!(min, 51580674);
+    assert_eq!(min, Some(51580674));
 }
 
 #[test]
 fn day_5_2() {
     let input = fs::read_to_string("data/day5.txt").unwrap();
     let min = min_location_ex2(&Almanac::parse(&input));
-    assert_eq!(min, 99751240);
+    assert_eq!(min, Some(99751240));
 }